@@ -0,0 +1,64 @@
+//! Benchmarks cooking large generated sources.
+
+use {
+  criterion::{criterion_group, criterion_main, Criterion},
+  ropey::Rope,
+  std::fmt::Write,
+  witch_cooking::Formatter,
+};
+
+/// Generates a Rust source with `n` functions spanning about `5 * n` lines.
+fn rust_src(n: usize) -> String {
+  (0..n).fold(String::new(), |mut src, ix| {
+    let _ = write!(
+      src,
+      "fn f{ix}(a: usize,b: usize) -> usize {{\n  let c = a+b;\n  g(c, \
+       {ix})\n}}\n\n",
+    );
+    src
+  })
+}
+
+fn spacer_root(c: &mut Criterion) {
+  let src = Rope::from(rust_src(4000));
+  let formatter = Formatter::new(
+    tree_sitter_rust::language(),
+    "((source_file) @root (#spacer! @root))",
+  )
+  .unwrap();
+
+  let mut group = c.benchmark_group("cook");
+  group.sample_size(10);
+  group.bench_function("spacer on 20k lines", |b| {
+    b.iter(|| formatter.format_rope(&src).unwrap())
+  });
+  group.finish();
+}
+
+fn alternatives(c: &mut Criterion) {
+  let src = Rope::from(rust_src(4000));
+  let formatter = Formatter::new(
+    tree_sitter_rust::language(),
+    r#"
+      (#set! cpl 32)
+      ((arguments) @args (#alternatives! @args "flat" "tall"))
+      ((arguments "," @comma . (_) @next) @args
+       (#set! @args alternative "flat")
+       (#space! @comma @next))
+      ((arguments "," @comma . (_) @next) @args
+       (#set! @args alternative "tall")
+       (#space! "\n    " @comma @next))
+    "#,
+  )
+  .unwrap();
+
+  let mut group = c.benchmark_group("cook");
+  group.sample_size(10);
+  group.bench_function("alternatives on 20k lines", |b| {
+    b.iter(|| formatter.format_rope(&src).unwrap())
+  });
+  group.finish();
+}
+
+criterion_group!(benches, spacer_root, alternatives);
+criterion_main!(benches);
@@ -0,0 +1,278 @@
+use {
+  crate::cli::{Error, Lang},
+  clap::ValueEnum,
+  error_stack::{IntoReport, Result, ResultExt},
+  fnv::FnvHashMap,
+  globset::{Glob, GlobMatcher},
+  serde::Deserialize,
+  std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+  },
+};
+
+/// Name of the configuration files.
+pub const FILENAME: &str = "cook.toml";
+
+/// Query file of the sources matching a glob, optionally of a language.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QueryEntry {
+  Path(PathBuf),
+  Table { query: PathBuf, lang: Option<String> },
+}
+
+/// Content of a configuration file.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct File {
+  /// Query files of the sources of a language, or matching a glob.
+  queries: BTreeMap<String, QueryEntry>,
+
+  /// Default global settings.
+  settings: BTreeMap<String, toml::Value>,
+
+  /// Whether each predicate is enabled.
+  predicates: BTreeMap<String, bool>,
+}
+
+/// Sources a query file applies to.
+enum Target {
+  Lang(Lang),
+  Glob { dir: PathBuf, glob: GlobMatcher, lang: Option<Lang> },
+}
+
+impl Target {
+  /// Returns whether the source at `path` of language `lang`, if known, is
+  /// targeted.
+  ///
+  /// A glob of a language only targets sources of that language.
+  fn targets(&self, path: Option<&Path>, lang: Option<Lang>) -> bool {
+    match self {
+      Self::Lang(target) => lang == Some(*target),
+      Self::Glob { dir, glob, lang: target } => {
+        target.is_none_or(|target| lang == Some(target))
+          && path
+            .and_then(|path| path.strip_prefix(dir).ok())
+            .is_some_and(|path| glob.is_match(path))
+      }
+    }
+  }
+}
+
+/// Parses `s` into a [`Lang`], ignoring its case.
+fn parse_lang(s: &str) -> Result<Lang, Error> {
+  Lang::from_str(s, true).map_err(|_| Error::lang_unsupported(s)).report()
+}
+
+/// Configuration of the sources of a directory, merged from the configuration
+/// files of the directory and of its ancestors, the nearest ones taking
+/// precedence.
+#[derive(Default)]
+pub struct Config {
+  /// Directory of the nearest configuration file, if any.
+  pub dir: Option<PathBuf>,
+
+  /// Query files, searched in order.
+  queries: Vec<(Target, PathBuf)>,
+
+  /// Default global settings.
+  pub settings: BTreeMap<String, String>,
+
+  /// Whether each predicate is enabled.
+  pub predicates: BTreeMap<String, bool>,
+}
+
+impl Config {
+  /// Returns this configuration overridden by `file`, located in `dir`.
+  fn merge(&self, dir: &Path, file: File) -> Result<Self, Error> {
+    let mut globs = Vec::new();
+    let mut langs = Vec::new();
+    for (key, entry) in file.queries {
+      let (query, lang) = match entry {
+        QueryEntry::Path(query) => (query, None),
+        QueryEntry::Table { query, lang } => (query, lang),
+      };
+      let query = dir.join(query);
+      match (parse_lang(&key), lang) {
+        (Ok(lang), None) => langs.push((Target::Lang(lang), query)),
+        (_, lang) => {
+          let glob = Glob::new(&key)
+            .report()
+            .attach_printable_lazy(|| key.clone())
+            .change_context(Error::Glob)?
+            .compile_matcher();
+          let lang = lang.as_deref().map(parse_lang).transpose()?;
+          let dir = dir.to_path_buf();
+          globs.push((Target::Glob { dir, glob, lang }, query));
+        }
+      }
+    }
+
+    let queries = globs.into_iter().chain(langs).chain(
+      self.queries.iter().map(|(target, query)| {
+        let target = match target {
+          Target::Lang(lang) => Target::Lang(*lang),
+          Target::Glob { dir, glob, lang } => {
+            Target::Glob { dir: dir.clone(), glob: glob.clone(), lang: *lang }
+          }
+        };
+        (target, query.clone())
+      }),
+    );
+
+    let mut settings = self.settings.clone();
+    settings.extend(file.settings.into_iter().map(|(key, value)| {
+      let value = match value {
+        toml::Value::String(s) => s,
+        value => value.to_string(),
+      };
+      (key, value)
+    }));
+    let mut predicates = self.predicates.clone();
+    predicates.extend(file.predicates);
+
+    Ok(Self {
+      dir: Some(dir.to_path_buf()),
+      queries: queries.collect(),
+      settings,
+      predicates,
+    })
+  }
+
+  /// Returns the language set for the source at `path`, if any.
+  pub fn lang(&self, path: &Path) -> Option<Lang> {
+    self.queries.iter().find_map(|(target, _)| match target {
+      Target::Glob { lang: Some(lang), .. }
+        if target.targets(Some(path), Some(*lang)) =>
+      {
+        Some(*lang)
+      }
+      _ => None,
+    })
+  }
+
+  /// Returns the query file of the source at `path`, if any, of language
+  /// `lang`.
+  pub fn query(&self, path: Option<&Path>, lang: Lang) -> Option<&Path> {
+    self
+      .queries
+      .iter()
+      .find(|(target, _)| target.targets(path, Some(lang)))
+      .map(|(_, query)| query.as_path())
+  }
+}
+
+/// Configurations of directories, cached as they are discovered.
+#[derive(Default)]
+pub struct Configs(FnvHashMap<PathBuf, Rc<Config>>);
+
+impl Configs {
+  /// Returns the configuration of the absolute directory `dir`.
+  pub fn get(&mut self, dir: &Path) -> Result<Rc<Config>, Error> {
+    if let Some(config) = self.0.get(dir) {
+      return Ok(config.clone());
+    }
+
+    let parent = match dir.parent() {
+      None => Rc::default(),
+      Some(parent) => self.get(parent)?,
+    };
+    let path = dir.join(FILENAME);
+    let config = match path.is_file() {
+      false => parent,
+      true => {
+        log::info!("reading configuration from {}", path.display());
+        let file = fs::read_to_string(&path)
+          .report()
+          .attach_printable_lazy(|| path.display().to_string())
+          .change_context(Error::Config)?;
+        let file = toml::from_str(&file)
+          .report()
+          .attach_printable_lazy(|| path.display().to_string())
+          .change_context(Error::Config)?;
+        Rc::new(parent.merge(dir, file)?)
+      }
+    };
+    self.0.insert(dir.to_path_buf(), config.clone());
+    Ok(config)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn configs() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path().canonicalize().unwrap();
+    let sub = root.join("sub");
+    fs::create_dir_all(sub.join("deep")).unwrap();
+    fs::write(
+      root.join(FILENAME),
+      r#"
+        [queries]
+        rust = "rust.scm"
+        "gen/*.rs" = "gen.scm"
+        "*.in" = {query = "in.scm", lang = "rust"}
+
+        [settings]
+        cpl = 79
+        indent-style = "  "
+
+        [predicates]
+        "align!" = false
+      "#,
+    )
+    .unwrap();
+    fs::write(
+      sub.join(FILENAME),
+      r#"
+        [queries]
+        rust = "sub.scm"
+
+        [settings]
+        indent-style = "\t"
+
+        [predicates]
+        "align!" = true
+        "spacer!" = false
+      "#,
+    )
+    .unwrap();
+
+    let mut configs = Configs::default();
+    let config = configs.get(&root).unwrap();
+    assert_eq!(config.dir.as_deref(), Some(root.as_path()));
+    assert_eq!(config.settings["cpl"], "79");
+    assert_eq!(config.settings["indent-style"], "  ");
+    assert!(!config.predicates["align!"]);
+    let query = |config: &Config, path: &str| {
+      let path = root.join(path);
+      let lang = config.lang(&path).unwrap_or(Lang::Rust);
+      config.query(Some(&path), lang).map(Path::to_path_buf)
+    };
+    assert_eq!(query(&config, "a.rs"), Some(root.join("rust.scm")));
+    assert_eq!(query(&config, "gen/a.rs"), Some(root.join("gen.scm")));
+    assert_eq!(config.lang(&root.join("a.in")), Some(Lang::Rust));
+    assert_eq!(query(&config, "a.in"), Some(root.join("in.scm")));
+    #[cfg(feature = "python")]
+    assert_eq!(config.query(Some(&root.join("a.in")), Lang::Python), None);
+
+    let config = configs.get(&sub.join("deep")).unwrap();
+    assert_eq!(config.dir.as_deref(), Some(sub.as_path()));
+    assert_eq!(config.settings["cpl"], "79");
+    assert_eq!(config.settings["indent-style"], "\t");
+    assert!(config.predicates["align!"]);
+    assert!(!config.predicates["spacer!"]);
+    assert_eq!(query(&config, "sub/a.rs"), Some(sub.join("sub.scm")));
+    assert_eq!(config.lang(&sub.join("deep/a.in")), Some(Lang::Rust));
+    assert_eq!(query(&config, "sub/deep/a.in"), Some(sub.join("sub.scm")));
+
+    fs::write(sub.join(FILENAME), "[queries]\nrust = 1\n").unwrap();
+    assert!(Configs::default().get(&sub).is_err());
+  }
+}
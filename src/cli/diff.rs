@@ -0,0 +1,61 @@
+use {ropey::Rope, similar::TextDiff};
+
+const BOLD: &str = "\x1b[1m";
+const CYAN: &str = "\x1b[36m";
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Returns the unified diff between `src` and `out`, the output of cooking
+/// the source `name`, colored with ANSI escape codes if `color`.
+pub fn diff(name: &str, src: &Rope, out: &Rope, color: bool) -> String {
+  let (src, out) = (src.to_string(), out.to_string());
+  let text_diff = TextDiff::from_lines(&src, &out);
+  let unified = text_diff.unified_diff().header(name, name).to_string();
+  if !color {
+    return unified;
+  }
+
+  unified
+    .split_inclusive('\n')
+    .enumerate()
+    .map(|(ix, line)| {
+      let style = match line.as_bytes().first() {
+        _ if ix < 2 => BOLD,
+        Some(b'@') => CYAN,
+        Some(b'+') => GREEN,
+        Some(b'-') => RED,
+        _ => return line.to_string(),
+      };
+      let (line, newline) = match line.strip_suffix('\n') {
+        None => (line, ""),
+        Some(line) => (line, "\n"),
+      };
+      format!("{style}{line}{RESET}{newline}")
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn diff_() {
+    let src = Rope::from_str("fn  f() {}\n--x;\n");
+    let out = Rope::from_str("fn f() {}\n--x;\n");
+    assert_eq!(
+      diff("f.rs", &src, &out, false),
+      "--- f.rs\n+++ f.rs\n@@ -1,2 +1,2 @@\n-fn  f() {}\n+fn f() {}\n --x;\n",
+    );
+    assert_eq!(
+      diff("f.rs", &src, &out, true),
+      format!(
+        "{BOLD}--- f.rs{RESET}\n{BOLD}+++ f.rs{RESET}\n{CYAN}@@ -1,2 +1,2 \
+         @@{RESET}\n{RED}-fn  f() {{}}{RESET}\n{GREEN}+fn f() {{}}{RESET}\n \
+         --x;\n",
+      ),
+    );
+    assert_eq!(diff("f.rs", &out, &out, false), "");
+  }
+}
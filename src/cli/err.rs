@@ -0,0 +1,52 @@
+use {std::fmt::Display, witch_cooking::Error as FormatErr};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+  #[error("could not read configuration file")]
+  Config,
+
+  #[error("could not read list of source files")]
+  FilesFrom,
+
+  #[error("{0}")]
+  Format(FormatErr),
+
+  #[error("invalid glob")]
+  Glob,
+
+  #[error("language \"{0}\" is unsupported")]
+  LangUnsupported(String),
+
+  #[error("could not detect language")]
+  LangUndetected,
+
+  #[error("failed during execution")]
+  Other,
+
+  #[error("no input piped")]
+  Pipe,
+
+  #[error("multiple source files require --write, --check or --diff")]
+  Srcs,
+
+  #[error("could not open query file")]
+  QueryFile,
+
+  #[error("no query file given or configured")]
+  QueryMissing,
+
+  #[error("could not open source file")]
+  SrcFile,
+
+  #[error("could not walk source directory")]
+  Walk,
+
+  #[error("could not write source file")]
+  Write,
+}
+
+impl Error {
+  pub fn lang_unsupported(lang: impl Display) -> Self {
+    Self::LangUnsupported(lang.to_string())
+  }
+}
@@ -0,0 +1,199 @@
+mod config;
+mod diff;
+mod err;
+mod paths;
+mod write;
+
+use {
+  clap::{ArgGroup, Parser, ValueEnum},
+  detect_lang::Language as DlLang,
+  std::{num::NonZeroUsize, path::PathBuf},
+  tree_sitter::Language as TsLang,
+  witch_cooking::editor::Policy,
+};
+pub use {
+  config::{Config, Configs},
+  diff::diff,
+  err::Error,
+  paths::{files_from, walk, Filter},
+  write::{write, Outcome, Summary},
+};
+
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, ValueEnum)]
+pub enum Lang {
+  #[cfg(feature = "bash")]
+  Bash,
+  #[cfg(feature = "c")]
+  C,
+  #[cfg(feature = "cpp")]
+  Cpp,
+  #[cfg(feature = "go")]
+  Go,
+  #[cfg(feature = "html")]
+  Html,
+  #[cfg(feature = "java")]
+  Java,
+  #[cfg(feature = "javascript")]
+  JavaScript,
+  #[cfg(feature = "python")]
+  Python,
+  #[cfg(feature = "rust")]
+  Rust,
+  #[cfg(feature = "toml")]
+  Toml,
+}
+
+impl<'a> TryFrom<DlLang<'a>> for Lang {
+  type Error = Error;
+
+  fn try_from(lang: DlLang<'_>) -> Result<Self, Self::Error> {
+    Ok(match lang.name() {
+      #[cfg(feature = "bash")]
+      "Shell" => Lang::Bash,
+      #[cfg(feature = "c")]
+      "C" => Lang::C,
+      #[cfg(feature = "cpp")]
+      "C++" => Lang::Cpp,
+      #[cfg(feature = "go")]
+      "Go" => Lang::Go,
+      #[cfg(feature = "html")]
+      "HTML" => Lang::Html,
+      #[cfg(feature = "java")]
+      "Java" => Lang::Java,
+      #[cfg(feature = "javascript")]
+      "JavaScript" => Lang::JavaScript,
+      #[cfg(feature = "python")]
+      "Python" => Lang::Python,
+      #[cfg(feature = "rust")]
+      "Rust" => Lang::Rust,
+      #[cfg(feature = "toml")]
+      "TOML" => Lang::Toml,
+      name => return Err(Error::lang_unsupported(name)),
+    })
+  }
+}
+
+impl From<Lang> for TsLang {
+  fn from(lang: Lang) -> Self {
+    match lang {
+      #[cfg(feature = "bash")]
+      Lang::Bash => tree_sitter_bash::language(),
+      #[cfg(feature = "c")]
+      Lang::C => tree_sitter_c::language(),
+      #[cfg(feature = "cpp")]
+      Lang::Cpp => tree_sitter_cpp::language(),
+      #[cfg(feature = "go")]
+      Lang::Go => tree_sitter_go::language(),
+      #[cfg(feature = "html")]
+      Lang::Html => tree_sitter_html::language(),
+      #[cfg(feature = "java")]
+      Lang::Java => tree_sitter_java::language(),
+      #[cfg(feature = "javascript")]
+      Lang::JavaScript => tree_sitter_javascript::language(),
+      #[cfg(feature = "python")]
+      Lang::Python => tree_sitter_python::language(),
+      #[cfg(feature = "rust")]
+      Lang::Rust => tree_sitter_rust::language(),
+      #[cfg(feature = "toml")]
+      Lang::Toml => tree_sitter_toml::language(),
+    }
+  }
+}
+
+/// Policy resolving overlapping edits.
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum Conflicts {
+  /// Later edits supersede the earlier ones they overlap.
+  LastWins,
+
+  /// Overlapping edits are an error, unless identical.
+  Strict,
+}
+
+impl From<Conflicts> for Policy {
+  fn from(conflicts: Conflicts) -> Self {
+    match conflicts {
+      Conflicts::LastWins => Policy::LastWins,
+      Conflicts::Strict => Policy::Strict,
+    }
+  }
+}
+
+/// Cooking the source code.
+#[derive(Debug, Parser)]
+#[command(group(ArgGroup::new("inputs").args(["srcs", "files_from"]).multiple(true)))]
+pub struct Opts {
+  /// Checks that the sources are cooked, printing those which are not,
+  /// without writing them.
+  ///
+  /// Exits with 1 if any source would change, and with 2 on errors.
+  #[arg(long, conflicts_with = "write")]
+  pub check: bool,
+
+  /// Checks that cooking the output again leaves it as is.
+  #[arg(long)]
+  pub check_idempotent: bool,
+
+  /// Policy resolving overlapping edits.
+  #[arg(long, value_enum, default_value_t = Conflicts::LastWins)]
+  pub conflicts: Conflicts,
+
+  /// Prints the unified diff between the sources and their outputs instead
+  /// of the outputs, without writing them.
+  #[arg(long, conflicts_with = "write")]
+  pub diff: bool,
+
+  /// Excludes the source files matching the glob.
+  #[arg(long, value_name = "GLOB")]
+  pub exclude: Vec<String>,
+
+  /// Cooks the source files listed in the file, separated by newlines or
+  /// NULs, or from the standard input if `-`.
+  #[arg(long, value_name = "PATH")]
+  pub files_from: Option<PathBuf>,
+
+  /// Reparses and cooks the output again until it stops changing.
+  #[arg(long)]
+  pub fixpoint: bool,
+
+  /// Only includes the source files matching any such glob.
+  #[arg(long, value_name = "GLOB")]
+  pub include: Vec<String>,
+
+  /// Language to parse.
+  #[arg(short, value_enum)]
+  pub lang: Option<Lang>,
+
+  /// Maximum number of passes when cooking until a fixpoint.
+  #[arg(long, default_value = "16", requires = "fixpoint")]
+  pub max_passes: NonZeroUsize,
+
+  /// Lists the builtin predicates and settings, then exits.
+  #[arg(long)]
+  pub list: bool,
+
+  /// Emits the output without checking that its tokens are preserved.
+  #[arg(long)]
+  pub no_verify: bool,
+
+  /// Query file, overriding those configured.
+  ///
+  /// Otherwise, the query file of each source is that mapped to its path or
+  /// language by the nearest `cook.toml` file, searched upward from the
+  /// source directory, which may also set default settings and disable
+  /// predicates. Nested `cook.toml` files override their ancestors.
+  #[arg(short)]
+  pub query: Option<PathBuf>,
+
+  /// Source files or directories, walked recursively, read from the standard
+  /// input if none.
+  ///
+  /// Files ignored by `.gitignore` or `.cookignore` files, and those of an
+  /// unsupported language, are skipped when walking directories. Multiple
+  /// source files require `--write`, `--check` or `--diff`.
+  pub srcs: Vec<PathBuf>,
+
+  /// Cooks the source files in place instead of printing them.
+  #[arg(long, requires = "inputs")]
+  pub write: bool,
+}
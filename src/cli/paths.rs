@@ -0,0 +1,216 @@
+use {
+  crate::cli::{config::FILENAME, Configs, Error, Lang},
+  error_stack::{IntoReport, Result, ResultExt},
+  globset::{Glob, GlobSet, GlobSetBuilder},
+  ignore::WalkBuilder,
+  std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+  },
+};
+
+/// Name of the files of patterns of paths to ignore, as in `.gitignore`.
+const IGNORE_FILENAME: &str = ".cookignore";
+
+/// Filter of paths by globs.
+pub struct Filter {
+  include: GlobSet,
+  exclude: GlobSet,
+}
+
+impl Filter {
+  /// Creates a filter keeping the paths matching any of `include`, if any,
+  /// but none of `exclude`.
+  pub fn new(include: &[String], exclude: &[String]) -> Result<Self, Error> {
+    let set = |globs: &[String]| {
+      let mut builder = GlobSetBuilder::new();
+      for glob in globs {
+        builder.add(
+          Glob::new(glob)
+            .report()
+            .attach_printable_lazy(|| glob.clone())
+            .change_context(Error::Glob)?,
+        );
+      }
+      builder.build().report().change_context(Error::Glob)
+    };
+    Ok(Self { include: set(include)?, exclude: set(exclude)? })
+  }
+
+  /// Returns whether `path` is kept.
+  pub fn keeps(&self, path: &Path) -> bool {
+    (self.include.is_empty() || self.include.is_match(path))
+      && !self.exclude.is_match(path)
+  }
+}
+
+/// Returns the paths listed in `s`, separated by NULs if any, or by newlines.
+fn split_list(s: &str) -> Vec<PathBuf> {
+  let sep = match s.contains('\0') {
+    false => '\n',
+    true => '\0',
+  };
+  s.split(sep)
+    .map(|path| path.strip_suffix('\r').unwrap_or(path))
+    .filter(|path| !path.is_empty())
+    .map(PathBuf::from)
+    .collect()
+}
+
+/// Returns the paths listed in the file at `path`, or from the standard input
+/// if `path` is `-`.
+pub fn files_from(path: &Path) -> Result<Vec<PathBuf>, Error> {
+  let list = match path.as_os_str() == "-" {
+    false => fs::read_to_string(path),
+    true => {
+      let mut list = String::new();
+      io::stdin().read_to_string(&mut list).map(|_| list)
+    }
+  }
+  .report()
+  .attach_printable_lazy(|| path.display().to_string())
+  .change_context(Error::FilesFrom)?;
+  Ok(split_list(&list))
+}
+
+/// Returns whether the language of `path`, either set by its configuration in
+/// `configs` or detected, is supported, and is `lang` if given.
+///
+/// Unless `has_query`, the language must also have a query file configured for
+/// `path`.
+fn is_supported(
+  path: &Path,
+  lang: Option<Lang>,
+  has_query: bool,
+  configs: &mut Configs,
+) -> Result<bool, Error> {
+  let real_path = fs::canonicalize(path)
+    .report()
+    .attach_printable_lazy(|| path.display().to_string())
+    .change_context(Error::Walk)?;
+  let config = match real_path.parent() {
+    None => return Ok(false),
+    Some(dir) => configs.get(dir)?,
+  };
+  let detected = match config.lang(&real_path) {
+    None => match detect_lang::from_path(path).map(Lang::try_from) {
+      Some(Ok(detected)) => detected,
+      _ => return Ok(false),
+    },
+    Some(configured) => configured,
+  };
+  Ok(
+    lang.is_none_or(|lang| lang == detected)
+      && (has_query || config.query(Some(&real_path), detected).is_some()),
+  )
+}
+
+/// Returns the source files under `dir` whose language is supported, and is
+/// `lang` if given, skipping those ignored by `.gitignore` or `.cookignore`
+/// files, or by `filter`, and the configuration files.
+///
+/// Languages set by the configurations in `configs` take precedence over the
+/// detected ones. Unless `has_query`, the files without a query file
+/// configured are skipped too.
+pub fn walk(
+  dir: &Path,
+  lang: Option<Lang>,
+  has_query: bool,
+  filter: &Filter,
+  configs: &mut Configs,
+) -> Result<Vec<PathBuf>, Error> {
+  let mut paths = Vec::new();
+  let walk = WalkBuilder::new(dir)
+    .add_custom_ignore_filename(IGNORE_FILENAME)
+    .require_git(false)
+    .sort_by_file_name(Ord::cmp)
+    .build();
+  for entry in walk {
+    let entry = entry
+      .report()
+      .attach_printable_lazy(|| dir.display().to_string())
+      .change_context(Error::Walk)?;
+    let path = entry.path();
+    if !entry.file_type().is_some_and(|file_type| file_type.is_file())
+      || path.file_name().is_some_and(|name| name == FILENAME)
+    {
+      continue;
+    }
+    match filter.keeps(path) && is_supported(path, lang, has_query, configs)? {
+      false => log::trace!("skipping {}", path.display()),
+      true => paths.push(path.to_path_buf()),
+    }
+  }
+  Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn split_list_() {
+    let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect();
+    let expected: Vec<PathBuf> = paths(&["a.rs", "b c.rs"]);
+    assert_eq!(split_list("a.rs\nb c.rs\n"), expected);
+    assert_eq!(split_list("a.rs\r\n\nb c.rs"), expected);
+    assert_eq!(split_list("a.rs\0b c.rs\0"), expected);
+    assert_eq!(split_list(""), paths(&[]));
+  }
+
+  #[test]
+  fn walk_() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    for path in [
+      "a.rs",
+      "b.toml",
+      "b.txt",
+      "c.py",
+      "gen/c.rs",
+      "gen/g.in",
+      "h.in",
+      "ignored/d.rs",
+      "e.rs",
+      "f.rs",
+    ] {
+      let path = root.join(path);
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, "").unwrap();
+    }
+    fs::write(root.join(".gitignore"), "ignored/\n").unwrap();
+    fs::write(root.join(".cookignore"), "e.rs\n").unwrap();
+    fs::write(
+      root.join("cook.toml"),
+      r#"
+        [queries]
+        rust = "q.scm"
+        "gen/*.in" = {query = "q.scm", lang = "rust"}
+      "#,
+    )
+    .unwrap();
+
+    let walk = |include: &[&str], exclude: &[&str], has_query| {
+      let globs = |globs: &[&str]| {
+        globs.iter().map(|s| s.to_string()).collect::<Vec<_>>()
+      };
+      let filter = Filter::new(&globs(include), &globs(exclude)).unwrap();
+      walk(root, None, has_query, &filter, &mut Configs::default())
+        .unwrap()
+        .into_iter()
+        .map(|path| path.strip_prefix(root).unwrap().to_path_buf())
+        .collect::<Vec<_>>()
+    };
+    let paths =
+      |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+    assert_eq!(
+      walk(&[], &[], false),
+      paths(&["a.rs", "f.rs", "gen/c.rs", "gen/g.in"]),
+    );
+    assert_eq!(walk(&[], &["**/gen/**"], false), paths(&["a.rs", "f.rs"]));
+    assert_eq!(walk(&["**/gen/*.rs"], &[], false), paths(&["gen/c.rs"]));
+    #[cfg(feature = "toml")]
+    assert_eq!(walk(&["**/*.toml"], &[], true), paths(&["b.toml"]));
+  }
+}
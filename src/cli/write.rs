@@ -0,0 +1,140 @@
+use {
+  ropey::Rope,
+  std::{fmt, fs, io, path::Path},
+  tempfile::NamedTempFile,
+};
+
+/// Outcome of cooking a source file in place.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Outcome {
+  /// The file was rewritten.
+  Changed,
+
+  /// The output was identical, so the file was left untouched.
+  Unchanged,
+}
+
+impl Outcome {
+  /// Returns the outcome of cooking `src` into `out`.
+  #[inline]
+  pub fn of(src: &Rope, out: &Rope) -> Self {
+    match src == out {
+      false => Self::Changed,
+      true => Self::Unchanged,
+    }
+  }
+}
+
+/// Counts of the outcomes of cooking source files in place, or of checking
+/// them when `dry`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Summary {
+  pub changed: usize,
+  pub unchanged: usize,
+  pub failed: usize,
+  pub dry: bool,
+}
+
+impl Summary {
+  /// Creates an empty summary, of checks if `dry`.
+  #[inline]
+  pub fn new(dry: bool) -> Self { Self { dry, ..Default::default() } }
+
+  /// Counts `outcome`, where [`None`] stands for a failure.
+  pub fn count(&mut self, outcome: Option<Outcome>) {
+    match outcome {
+      None => self.failed += 1,
+      Some(Outcome::Changed) => self.changed += 1,
+      Some(Outcome::Unchanged) => self.unchanged += 1,
+    }
+  }
+}
+
+impl fmt::Display for Summary {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let files = |n| match n {
+      1 => "1 file".to_string(),
+      n => format!("{n} files"),
+    };
+    write!(
+      f,
+      "{} {}, {} unchanged",
+      files(self.changed),
+      match self.dry {
+        false => "changed",
+        true => "would change",
+      },
+      files(self.unchanged),
+    )?;
+    if self.failed > 0 {
+      write!(f, ", {} failed", files(self.failed))?;
+    }
+    Ok(())
+  }
+}
+
+/// Writes `out` to `path` in place of `src`, its current content, unless both
+/// are identical.
+///
+/// The output is written to a temporary file next to the file `path` resolves
+/// to, given its permissions and then renamed over it, so that the file is
+/// never left half written and symbolic links are preserved.
+pub fn write(path: &Path, src: &Rope, out: &Rope) -> io::Result<Outcome> {
+  if Outcome::of(src, out) == Outcome::Unchanged {
+    log::info!("{} unchanged", path.display());
+    return Ok(Outcome::Unchanged);
+  }
+
+  let real_path = fs::canonicalize(path)?;
+  let dir = real_path.parent().unwrap_or(Path::new("/"));
+  let mut file = NamedTempFile::new_in(dir)?;
+  out.write_to(io::BufWriter::new(file.as_file_mut()))?;
+  file.as_file().sync_all()?;
+  fs::set_permissions(file.path(), fs::metadata(&real_path)?.permissions())?;
+  file.persist(&real_path).map_err(|err| err.error)?;
+  log::info!("{} changed", path.display());
+  Ok(Outcome::Changed)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn write_() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("src.rs");
+    fs::write(&path, "fn  f() {}").unwrap();
+    let mut perms = fs::metadata(&path).unwrap().permissions();
+    perms.set_readonly(true);
+    fs::set_permissions(&path, perms.clone()).unwrap();
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+
+    let src = Rope::from_str("fn  f() {}");
+    let out = Rope::from_str("fn f() {}");
+    assert_eq!(write(&path, &src, &src).unwrap(), Outcome::Unchanged);
+    assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+
+    assert_eq!(write(&path, &src, &out).unwrap(), Outcome::Changed);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "fn f() {}");
+    assert_eq!(fs::metadata(&path).unwrap().permissions(), perms);
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn write_symlink() {
+    let dir = tempfile::tempdir().unwrap();
+    let real_path = dir.path().join("real.rs");
+    let link = dir.path().join("link.rs");
+    fs::write(&real_path, "fn  f() {}").unwrap();
+    std::os::unix::fs::symlink(&real_path, &link).unwrap();
+
+    let src = Rope::from_str("fn  f() {}");
+    let out = Rope::from_str("fn f() {}");
+    assert_eq!(write(&link, &src, &out).unwrap(), Outcome::Changed);
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(&real_path).unwrap(), "fn f() {}");
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+  }
+}
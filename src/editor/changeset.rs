@@ -0,0 +1,454 @@
+use {
+  super::{end_point, err::Error, fenwick::Fenwick, Bias, Origin},
+  ropey::{Rope, RopeBuilder, RopeSlice},
+  std::{
+    collections::BTreeMap,
+    ops::{Bound, Range},
+  },
+  tree_sitter::{InputEdit, Point},
+};
+
+/// Key of a change, made of the start and end bytes of the original range it
+/// replaces.
+type Key = (usize, usize);
+
+/// Returns whether a change of `range` overlaps a prior change of `key`.
+///
+/// That is the case when both ranges overlap, or when the prior change is an
+/// insertion within `range`. Changes merely touching each other's boundaries
+/// do not overlap.
+#[inline]
+pub(super) fn overlaps(range: &Range<usize>, (start, end): Key) -> bool {
+  (start < range.end && end > range.start)
+    || (start == end && range.start <= start && start <= range.end)
+}
+
+/// Returns the number of line feeds in `s`.
+#[inline]
+fn line_feeds(s: &str) -> isize {
+  s.bytes().filter(|b| *b == b'\n').count() as _
+}
+
+/// Policy resolving a change which overlaps prior ones.
+///
+/// Whatever the policy, a change identical to a prior one, or one leaving the
+/// output as is, is merged into it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Policy {
+  /// The new change supersedes the prior ones it overlaps: the output they
+  /// produced is replaced as a whole.
+  #[default]
+  LastWins,
+
+  /// The new change is rejected with [`Error::Conflict`].
+  Strict,
+}
+
+/// Change of the original range `range` by `text`, made from `origin`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Change<'a> {
+  /// Original range replaced.
+  pub range: Range<usize>,
+
+  /// Text replacing the range.
+  pub text: &'a str,
+
+  /// Origin of the change, if known.
+  pub origin: Option<Origin>,
+}
+
+/// Text replacing an original range, along with its origin.
+#[derive(Clone, Debug)]
+struct Piece {
+  text: String,
+  origin: Option<Origin>,
+}
+
+/// Operation undoing a modification of the changes.
+#[derive(Clone, Debug)]
+enum Undo {
+  Insert(Key, Piece),
+  Remove(Key),
+}
+
+/// Set of non-overlapping changes of a source, addressed in original
+/// coordinates and applied all at once.
+///
+/// Changes are indexed by the original range they replace, so that mapping an
+/// original position to its location in the output takes logarithmic time
+/// regardless of how many changes were made.
+///
+/// Modifications made after a [`checkpoint`] are journaled, so that they can
+/// be rolled back in time proportional to their number.
+///
+/// [`checkpoint`]: Changeset::checkpoint
+#[derive(Clone)]
+pub struct Changeset {
+  src: Rope,
+  changes: BTreeMap<Key, Piece>,
+  bytes: Fenwick,
+  rows: Fenwick,
+  policy: Policy,
+  journal: Option<Vec<Undo>>,
+}
+
+impl Changeset {
+  /// Creates an empty changeset of `src`, resolving overlaps with `policy`.
+  pub fn new(src: Rope, policy: Policy) -> Self {
+    Self {
+      bytes: Fenwick::new(src.len_bytes() + 1),
+      rows: Fenwick::new(src.len_bytes() + 1),
+      src,
+      changes: BTreeMap::default(),
+      policy,
+      journal: None,
+    }
+  }
+
+  /// Returns the original source.
+  #[inline]
+  pub fn src(&self) -> RopeSlice<'_> { self.src.slice(..) }
+
+  /// Returns the changes in the order they apply.
+  pub fn iter(&self) -> impl Iterator<Item = Change<'_>> + '_ {
+    self.changes.iter().map(|((start, end), Piece { text, origin })| Change {
+      range: *start..*end,
+      text,
+      origin: *origin,
+    })
+  }
+
+  /// Returns whether there is no change.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.changes.is_empty() }
+
+  /// Returns the policy resolving overlaps.
+  #[inline]
+  pub fn policy(&self) -> Policy { self.policy }
+
+  /// Adds the change of the original `range` by `text`, made from `origin`,
+  /// resolving overlaps according to the [`Policy`].
+  pub fn push(
+    &mut self,
+    range: Range<usize>,
+    text: &str,
+    origin: Option<Origin>,
+  ) -> Result<(), Error> {
+    if self.text(range.clone()) == text {
+      log::trace!("merging no-op change of bytes {range:?}");
+      return Ok(());
+    }
+
+    let overlapped = self
+      .changes
+      .range(..=(range.end, usize::MAX))
+      .rev()
+      .take_while(|((_, end), _)| *end >= range.start)
+      .filter_map(|(key, _)| overlaps(&range, *key).then_some(*key))
+      .collect::<Vec<_>>();
+
+    if let (Policy::Strict, Some((start, end))) =
+      (self.policy, overlapped.first())
+    {
+      return Err(Error::Conflict { old: *start..*end, new: range });
+    }
+
+    let start = overlapped
+      .last()
+      .map_or(range.start, |(start, _)| range.start.min(*start));
+    let end =
+      overlapped.first().map_or(range.end, |(_, end)| range.end.max(*end));
+
+    overlapped.into_iter().for_each(|key| {
+      log::trace!("superseding change of bytes {:?}", key.0..key.1);
+      self.remove_piece(key);
+    });
+    self.insert_piece((start, end), Piece { text: text.to_string(), origin });
+    Ok(())
+  }
+
+  /// Removes the changes for which `keep` returns `false`.
+  pub fn retain(&mut self, mut keep: impl FnMut(&Change<'_>) -> bool) {
+    let removed = self
+      .iter()
+      .filter(|change| !keep(change))
+      .map(|Change { range, .. }| (range.start, range.end))
+      .collect::<Vec<_>>();
+    removed.into_iter().for_each(|key| self.remove_piece(key));
+  }
+
+  /// Starts journaling the modifications, forgetting those journaled so far.
+  #[inline]
+  pub fn checkpoint(&mut self) { self.journal = Some(Vec::new()) }
+
+  /// Undoes the modifications made since the last [`checkpoint`], and stops
+  /// journaling them.
+  ///
+  /// [`checkpoint`]: Changeset::checkpoint
+  pub fn rollback(&mut self) {
+    let journal = self.journal.take().unwrap_or_default();
+    journal.into_iter().rev().for_each(|undo| match undo {
+      Undo::Insert(key, piece) => self.insert_piece(key, piece),
+      Undo::Remove(key) => self.remove_piece(key),
+    });
+  }
+
+  /// Inserts `piece` replacing the original range of `key`, indexing it.
+  fn insert_piece(&mut self, key: Key, piece: Piece) {
+    self.index(key, &piece.text, 1);
+    self.changes.insert(key, piece);
+    if let Some(journal) = &mut self.journal {
+      journal.push(Undo::Remove(key));
+    }
+  }
+
+  /// Removes the piece replacing the original range of `key`, unindexing it.
+  fn remove_piece(&mut self, key: Key) {
+    let piece = self.changes.remove(&key).unwrap();
+    self.index(key, &piece.text, -1);
+    if let Some(journal) = &mut self.journal {
+      journal.push(Undo::Insert(key, piece));
+    }
+  }
+
+  /// Adds or removes, according to `sign`, the deltas of the change of `key`
+  /// by `text` to the indexes.
+  fn index(&mut self, key @ (start, end): Key, text: &str, sign: isize) {
+    let bytes = text.len() as isize - (end - start) as isize;
+    let rows = line_feeds(text) - (self.src.byte_to_line(end) as isize)
+      + self.src.byte_to_line(start) as isize;
+    self.bytes.add(key.1, sign * bytes);
+    self.rows.add(key.1, sign * rows);
+  }
+
+  /// Maps the original `byte` to its location in the output, measuring it with
+  /// `base` in the original source and with `weight` in changes, whose deltas
+  /// are indexed by `deltas`.
+  fn map_with(
+    &self,
+    byte: usize,
+    bias: Bias,
+    base: impl Fn(usize) -> isize,
+    deltas: &Fenwick,
+    weight: impl Fn(&str) -> isize,
+  ) -> usize {
+    let mut out = base(byte) + deltas.prefix(byte);
+
+    if bias == Bias::Right {
+      if let Some(piece) = self.changes.get(&(byte, byte)) {
+        out += weight(&piece.text);
+      }
+    }
+
+    if let Some(((start, end), piece)) =
+      self.changes.range(..(byte, byte)).next_back()
+    {
+      if *end == byte {
+        out += weight(&piece.text) - base(*end) + base(*start);
+      } else if *end > byte {
+        out = base(*start) + deltas.prefix(start + 1);
+        if bias == Bias::Right {
+          out += weight(&piece.text);
+        }
+      }
+    }
+
+    out as usize
+  }
+
+  /// Maps the original `byte` to its location in the output.
+  #[inline]
+  pub fn map_byte(&self, byte: usize, bias: Bias) -> usize {
+    self.map_with(byte, bias, |byte| byte as _, &self.bytes, |s| s.len() as _)
+  }
+
+  /// Maps the original `byte` to the row it is located at in the output.
+  #[inline]
+  pub fn map_row(&self, byte: usize, bias: Bias) -> usize {
+    let base = |byte| self.src.byte_to_line(byte) as _;
+    self.map_with(byte, bias, base, &self.rows, line_feeds)
+  }
+
+  /// Returns the output produced for the original `range`.
+  ///
+  /// The output spans from the location of the start of `range`, biased to
+  /// the left, to the location of its end, biased to the right.
+  pub fn text(&self, range: Range<usize>) -> String {
+    let mut text = String::new();
+    let mut byte = range.start;
+
+    if let Some(((start, end), piece)) =
+      self.changes.range(..(range.start, range.start)).next_back()
+    {
+      if *start < range.start && *end > range.start {
+        text.push_str(&piece.text);
+        byte = *end;
+      }
+    }
+
+    if byte > range.end {
+      return text;
+    }
+
+    self.changes.range((byte, byte)..=(range.end, range.end)).for_each(
+      |((start, end), piece)| {
+        text.extend(self.src.byte_slice(byte..*start).chunks());
+        text.push_str(&piece.text);
+        byte = *end;
+      },
+    );
+
+    if byte < range.end {
+      text.extend(self.src.byte_slice(byte..range.end).chunks());
+    }
+
+    text
+  }
+
+  /// Returns the output from the start of the line the original `byte` is
+  /// located at in the output, up to that location.
+  pub fn line_prefix(&self, byte: usize, bias: Bias) -> String {
+    let bound = match bias {
+      Bias::Left => Bound::Excluded((byte, byte)),
+      Bias::Right => Bound::Included((byte, byte)),
+    };
+    let mut changes =
+      self.changes.range((Bound::Unbounded, bound)).rev().peekable();
+    let mut pieces = Vec::<String>::new();
+    let mut byte = byte;
+
+    // Pushes the output chunk `s` up to its last line feed, returning whether
+    // it had one.
+    let push = |pieces: &mut Vec<String>, s: &str| match s.rfind('\n') {
+      None => {
+        pieces.push(s.to_string());
+        false
+      }
+      Some(ix) => {
+        pieces.push(s[ix + 1..].to_string());
+        true
+      }
+    };
+
+    // Pushes the original chunk `slice` up to its last line feed, returning
+    // whether it had one.
+    let push_slice = |pieces: &mut Vec<String>, slice: RopeSlice<'_>| {
+      let line_ix = slice.len_lines() - 1;
+      pieces.push(slice.byte_slice(slice.line_to_byte(line_ix)..).to_string());
+      line_ix > 0
+    };
+
+    let prefix = |pieces: Vec<String>| pieces.into_iter().rev().collect();
+
+    if let Some(((start, end), piece)) = changes.peek() {
+      if *start < byte && *end > byte {
+        let s = match bias {
+          Bias::Left => "",
+          Bias::Right => piece.text.as_str(),
+        };
+        byte = *start;
+        changes.next();
+        if push(&mut pieces, s) {
+          return prefix(pieces);
+        }
+      }
+    }
+
+    for ((start, end), piece) in changes {
+      if push_slice(&mut pieces, self.src.byte_slice(*end..byte))
+        || push(&mut pieces, &piece.text)
+      {
+        return prefix(pieces);
+      }
+      byte = *start;
+    }
+
+    push_slice(&mut pieces, self.src.byte_slice(..byte));
+    prefix(pieces)
+  }
+
+  /// Applies the changes to the original source.
+  pub fn apply(&self) -> Rope {
+    let mut builder = RopeBuilder::new();
+    let mut byte = 0;
+    self.iter().for_each(|change| {
+      let slice = self.src.byte_slice(byte..change.range.start);
+      slice.chunks().for_each(|chunk| builder.append(chunk));
+      builder.append(change.text);
+      byte = change.range.end;
+    });
+    let slice = self.src.byte_slice(byte..);
+    slice.chunks().for_each(|chunk| builder.append(chunk));
+    builder.finish()
+  }
+
+  /// Returns the edits turning a tree of the original source into one of
+  /// `out`, the output of [`apply`], in the order [`Tree::edit`] expects them.
+  ///
+  /// [`apply`]: Changeset::apply
+  /// [`Tree::edit`]: tree_sitter::Tree::edit
+  pub fn input_edits(&self, out: RopeSlice<'_>) -> Vec<InputEdit> {
+    let point = |byte| {
+      let row = out.byte_to_line(byte);
+      Point { row, column: byte - out.line_to_byte(row) }
+    };
+
+    let mut delta = 0;
+    self
+      .iter()
+      .map(|Change { range, text, .. }| {
+        let start_byte = (range.start as isize + delta) as usize;
+        let start_position = point(start_byte);
+        let old = self.src.byte_slice(range.clone());
+        let edit = InputEdit {
+          start_byte,
+          old_end_byte: start_byte + range.len(),
+          new_end_byte: start_byte + text.len(),
+          start_position,
+          old_end_position: end_point(old.chars(), Some(&start_position)),
+          new_end_position: point(start_byte + text.len()),
+        };
+        delta += text.len() as isize - range.len() as isize;
+        edit
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn policy() {
+    let push = |changes: &mut Changeset, range: Range<usize>, text| {
+      changes
+        .push(range, text, None)
+        .map_err(|Error::Conflict { old, new }| (old, new))
+    };
+
+    let mut changes = Changeset::new(Rope::from_str("a b c"), Policy::Strict);
+    assert_eq!(push(&mut changes, 1..2, "  "), Ok(()));
+    assert_eq!(push(&mut changes, 1..2, "  "), Ok(()));
+    assert_eq!(push(&mut changes, 2..3, "B"), Ok(()));
+    assert_eq!(push(&mut changes, 0..2, "a  "), Ok(()));
+    assert_eq!(push(&mut changes, 0..2, "A"), Err((1..2, 0..2)));
+    assert_eq!(push(&mut changes, 1..1, ","), Ok(()));
+    assert_eq!(push(&mut changes, 4..4, ";"), Ok(()));
+    assert_eq!(push(&mut changes, 4..4, ","), Err((4..4, 4..4)));
+    assert_eq!(changes.apply(), "a,  B ;c");
+
+    let mut changes =
+      Changeset::new(Rope::from_str("a b c"), Policy::LastWins);
+    assert_eq!(push(&mut changes, 1..2, "  "), Ok(()));
+    assert_eq!(push(&mut changes, 2..3, "B"), Ok(()));
+    assert_eq!(push(&mut changes, 0..2, "A"), Ok(()));
+    assert_eq!(push(&mut changes, 4..4, ","), Ok(()));
+    assert_eq!(changes.apply(), "AB ,c");
+    assert_eq!(changes.iter().collect::<Vec<_>>(), [
+      Change { range: 0..2, text: "A", origin: None },
+      Change { range: 2..3, text: "B", origin: None },
+      Change { range: 4..4, text: ",", origin: None },
+    ],);
+  }
+}
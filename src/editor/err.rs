@@ -0,0 +1,15 @@
+use std::ops::Range;
+
+/// Error of an [`Editor`].
+///
+/// [`Editor`]: crate::editor::Editor
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Error {
+  /// The change of the original range `new` overlaps the prior change of the
+  /// original range `old`.
+  #[allow(missing_docs)]
+  #[error(
+    "change of bytes {new:?} conflicts with prior change of bytes {old:?}"
+  )]
+  Conflict { old: Range<usize>, new: Range<usize> },
+}
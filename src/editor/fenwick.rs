@@ -0,0 +1,51 @@
+/// [Fenwick tree] of signed values supporting point updates and prefix sums
+/// in logarithmic time.
+///
+/// [Fenwick tree]: https://en.wikipedia.org/wiki/Fenwick_tree
+#[derive(Clone, Debug)]
+pub struct Fenwick(Vec<isize>);
+
+impl Fenwick {
+  /// Creates a tree holding `len` zeroed values.
+  #[inline]
+  pub fn new(len: usize) -> Self { Self(vec![0; len + 1]) }
+
+  /// Adds `delta` to the value at `ix`.
+  pub fn add(&mut self, ix: usize, delta: isize) {
+    let mut ix = ix + 1;
+    while ix < self.0.len() {
+      self.0[ix] += delta;
+      ix += ix & ix.wrapping_neg();
+    }
+  }
+
+  /// Returns the sum of the values before `ix`.
+  pub fn prefix(&self, ix: usize) -> isize {
+    let mut ix = ix.min(self.0.len() - 1);
+    let mut sum = 0;
+    while ix > 0 {
+      sum += self.0[ix];
+      ix -= ix & ix.wrapping_neg();
+    }
+    sum
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fenwick() {
+    let mut values = [0isize; 17];
+    let mut fenwick = Fenwick::new(values.len());
+
+    for (ix, delta) in [(0, 3), (16, -2), (7, 5), (8, -1), (7, 2), (0, -3)] {
+      values[ix] += delta;
+      fenwick.add(ix, delta);
+      (0..=values.len()).for_each(|ix| {
+        assert_eq!(values[..ix].iter().sum::<isize>(), fenwick.prefix(ix));
+      });
+    }
+  }
+}
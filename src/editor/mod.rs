@@ -0,0 +1,610 @@
+mod changeset;
+mod err;
+mod fenwick;
+
+pub use {
+  changeset::{Change, Changeset, Policy},
+  err::Error,
+};
+use {
+  ropey::{Rope, RopeSlice},
+  std::ops::Range as ByteRange,
+  tree_sitter::{InputEdit, Node, Point, Range},
+};
+
+#[inline]
+pub(crate) fn end_point(
+  chars: impl Iterator<Item = char>,
+  start: Option<&Point>,
+) -> Point {
+  let mut end = start.copied().unwrap_or_default();
+  chars.for_each(|ch| match ch {
+    '\n' => {
+      end.row += 1;
+      end.column = 0;
+    }
+    _ => end.column += ch.len_utf8(),
+  });
+  end
+}
+
+/// Returns whether a change of `range` would edit bytes within `ignored`.
+///
+/// Changes merely touching the boundaries of `ignored` leave it as is.
+#[inline]
+fn intrudes(range: &ByteRange<usize>, ignored: &ByteRange<usize>) -> bool {
+  match range.is_empty() {
+    false => range.start < ignored.end && ignored.start < range.end,
+    true => ignored.start < range.start && range.start < ignored.end,
+  }
+}
+
+/// Side an original position leans towards when an edit touches it.
+///
+/// An insertion made exactly at an original position, or a replacement
+/// covering it, leaves the position ambiguous: it may be located either before
+/// or after the new text. [`Left`] keeps it before, whereas [`Right`] moves it
+/// after.
+///
+/// [`Left`]: Bias::Left
+/// [`Right`]: Bias::Right
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Bias {
+  /// Leans towards the text before the position.
+  Left,
+
+  /// Leans towards the text after the position.
+  Right,
+}
+
+/// Origin of an edit: the predicate `pred_ix` of the pattern `pat_ix`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Origin {
+  /// Index of the pattern in the query.
+  pub pat_ix: usize,
+
+  /// Index of the predicate in the general predicates of the pattern.
+  pub pred_ix: usize,
+}
+
+/// State of an [`Editor`] to roll back to.
+pub struct Checkpoint {
+  ignored: usize,
+  origin: Option<Origin>,
+}
+
+/// Editor of a source whose edits are addressed in original coordinates.
+///
+/// Edits are not applied right away: they are recorded into a [`Changeset`],
+/// applied once the editor is turned into a [`Rope`]. Reads go through the
+/// changeset, so they reflect every edit made so far.
+///
+/// Edits within ignored ranges are suppressed.
+#[derive(Clone)]
+pub struct Editor {
+  changes: Changeset,
+  ignored: Vec<ByteRange<usize>>,
+  origin: Option<Origin>,
+}
+
+impl Editor {
+  /// Creates an editor of `src`, resolving overlapping edits with `policy`.
+  pub fn new(src: Rope, policy: Policy) -> Self {
+    Self {
+      changes: Changeset::new(src, policy),
+      ignored: Vec::new(),
+      origin: None,
+    }
+  }
+
+  /// Protects the original `range` from edits, dropping those made within it
+  /// so far.
+  pub fn ignore(&mut self, range: ByteRange<usize>) {
+    self.changes.retain(|change| {
+      let keep = !intrudes(&change.range, &range);
+      if !keep {
+        log::trace!(
+          "dropping change of bytes {:?} within ignored bytes {range:?}",
+          change.range,
+        );
+      }
+      keep
+    });
+    self.ignored.push(range);
+  }
+
+  /// Returns a checkpoint of the current state, journaling the edits made from
+  /// now on so that [`rollback`] undoes them.
+  ///
+  /// [`rollback`]: Editor::rollback
+  pub fn checkpoint(&mut self) -> Checkpoint {
+    self.changes.checkpoint();
+    Checkpoint { ignored: self.ignored.len(), origin: self.origin }
+  }
+
+  /// Undoes the edits and the ignored ranges made since `checkpoint`.
+  pub fn rollback(&mut self, checkpoint: Checkpoint) {
+    self.changes.rollback();
+    self.ignored.truncate(checkpoint.ignored);
+    self.origin = checkpoint.origin;
+  }
+
+  /// Returns the origin of the edits made from now on.
+  #[inline]
+  pub fn origin(&self) -> Option<Origin> { self.origin }
+
+  /// Sets the origin of the edits made from now on.
+  #[inline]
+  pub fn set_origin(&mut self, origin: Option<Origin>) {
+    self.origin = origin;
+  }
+
+  /// Inserts `s` at the original `byte`.
+  #[inline]
+  pub fn insert(&mut self, byte: usize, s: &str) -> Result<(), Error> {
+    self.replace(byte..byte, s)
+  }
+
+  /// Removes the original `range`.
+  #[inline]
+  pub fn remove(&mut self, range: ByteRange<usize>) -> Result<(), Error> {
+    self.replace(range, "")
+  }
+
+  /// Replaces the original `range` by `s`.
+  ///
+  /// Overlaps with prior edits are resolved according to the [`Policy`] of
+  /// the editor. Edits within ignored ranges are suppressed.
+  pub fn replace(
+    &mut self,
+    range: ByteRange<usize>,
+    s: &str,
+  ) -> Result<(), Error> {
+    if let Some(ignored) =
+      self.ignored.iter().find(|ignored| intrudes(&range, ignored))
+    {
+      log::trace!(
+        "suppressing change of bytes {range:?} within ignored bytes \
+         {ignored:?}"
+      );
+      return Ok(());
+    }
+    self.changes.push(range, s, self.origin)
+  }
+
+  /// Returns the original source.
+  #[inline]
+  pub fn src(&self) -> RopeSlice<'_> { self.changes.src() }
+
+  /// Returns the changes made so far.
+  #[inline]
+  pub fn changes(&self) -> &Changeset { &self.changes }
+
+  /// Returns the edits turning a tree of the original source into one of
+  /// `out`, the output of the editor.
+  #[inline]
+  pub fn input_edits(&self, out: RopeSlice<'_>) -> Vec<InputEdit> {
+    self.changes.input_edits(out)
+  }
+
+  /// Returns the current text produced for the original `range`.
+  ///
+  /// Text inserted at the boundaries of `range` is part of it.
+  #[inline]
+  pub fn text(&self, range: ByteRange<usize>) -> String {
+    self.changes.text(range)
+  }
+
+  /// Returns the current text from the start of the line the original `byte`
+  /// is located at, up to that location.
+  #[inline]
+  pub fn line_prefix(&self, byte: usize, bias: Bias) -> String {
+    self.changes.line_prefix(byte, bias)
+  }
+
+  /// Maps the original `byte` to its current location.
+  #[inline]
+  pub fn map_byte(&self, byte: usize, bias: Bias) -> usize {
+    self.changes.map_byte(byte, bias)
+  }
+
+  /// Returns the current point of the original `byte`.
+  #[inline]
+  fn point(&self, byte: usize, bias: Bias) -> Point {
+    Point {
+      row: self.changes.map_row(byte, bias),
+      column: self.line_prefix(byte, bias).len(),
+    }
+  }
+
+  /// Maps the original `point` to its current location.
+  pub fn map_point(&self, point: &Point, bias: Bias) -> Point {
+    self.point(self.src().line_to_byte(point.row) + point.column, bias)
+  }
+
+  /// Maps the original `range` to its current location, biasing its start and
+  /// end respectively with `start_bias` and `end_bias`.
+  pub fn map_range(
+    &self,
+    range: &Range,
+    start_bias: Bias,
+    end_bias: Bias,
+  ) -> Range {
+    Range {
+      start_byte: self.map_byte(range.start_byte, start_bias),
+      end_byte: self.map_byte(range.end_byte, end_bias),
+      start_point: self.point(range.start_byte, start_bias),
+      end_point: self.point(range.end_byte, end_bias),
+    }
+  }
+
+  /// Returns the current range of `node`.
+  ///
+  /// Text inserted at the boundaries of `node` is not part of it.
+  #[inline]
+  pub fn node_range(&self, node: &Node<'_>) -> Range {
+    self.map_range(&node.range(), Bias::Right, Bias::Left)
+  }
+
+  /// Returns the current range between the end of `a` and the start of `b`.
+  ///
+  /// Text inserted at the boundaries of the gap is part of it.
+  #[inline]
+  pub fn gap_range(&self, a: &Node<'_>, b: &Node<'_>) -> Range {
+    let gap = Range {
+      start_byte: a.end_byte(),
+      end_byte: b.start_byte(),
+      start_point: a.end_position(),
+      end_point: b.start_position(),
+    };
+    self.map_range(&gap, Bias::Left, Bias::Right)
+  }
+}
+
+impl From<Editor> for Rope {
+  fn from(editor: Editor) -> Self { editor.changes.apply() }
+}
+
+impl From<Rope> for Editor {
+  fn from(src: Rope) -> Self { Self::new(src, Policy::default()) }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::{changeset::overlaps, *},
+    crate::node_utils::Walker,
+    tree_sitter::{Parser, Tree},
+    tree_sitter_rust::language as rust_lang,
+  };
+
+  fn output(editor: &Editor) -> String { editor.changes.apply().to_string() }
+
+  #[test]
+  fn end_point_() {
+    let test = |s: &str, end| assert_eq!(end_point(s.chars(), None), end);
+    test("💣💥", Point { row: 0, column: 8 });
+    test("\n", Point { row: 1, column: 0 });
+    test("\r\u{b}\u{c}\u{85}\u{2028}\u{2029}", Point { row: 0, column: 11 });
+  }
+
+  #[test]
+  fn map_bias() {
+    let mut editor = Editor::from(Rope::from_str("fn f() {}"));
+    let point = |column| Point { row: 0, column };
+    let test = |editor: &Editor, byte, left, right| {
+      assert_eq!(left, editor.map_byte(byte, Bias::Left));
+      assert_eq!(right, editor.map_byte(byte, Bias::Right));
+      assert_eq!(point(left), editor.map_point(&point(byte), Bias::Left));
+      assert_eq!(point(right), editor.map_point(&point(byte), Bias::Right));
+    };
+
+    editor.insert(5, "x: X").unwrap();
+    test(&editor, 4, 4, 4);
+    test(&editor, 5, 5, 9);
+    test(&editor, 6, 10, 10);
+
+    editor.replace(6..7, "\n").unwrap();
+    assert_eq!("fn f(x: X)\n{}", output(&editor));
+    test(&editor, 5, 5, 9);
+    assert_eq!(
+      Point { row: 1, column: 0 },
+      editor.map_point(&point(7), Bias::Right),
+    );
+    assert_eq!(11, editor.map_byte(7, Bias::Right));
+
+    editor.remove(2..4).unwrap();
+    assert_eq!("fn(x: X)\n{}", output(&editor));
+    test(&editor, 2, 2, 2);
+    test(&editor, 3, 2, 2);
+    test(&editor, 4, 2, 2);
+    test(&editor, 5, 3, 7);
+  }
+
+  #[test]
+  fn ignore() {
+    let mut editor = Editor::from(Rope::from_str("a  bb  c"));
+    editor.replace(1..3, " ").unwrap();
+    editor.replace(4..4, "x").unwrap();
+    editor.ignore(3..5);
+    assert_eq!("a bb  c", output(&editor));
+
+    editor.replace(3..5, "B").unwrap();
+    editor.insert(4, "x").unwrap();
+    editor.replace(0..8, "").unwrap();
+    editor.replace(5..7, " ").unwrap();
+    editor.insert(3, "(").unwrap();
+    assert_eq!("a (bb c", output(&editor));
+  }
+
+  #[test]
+  fn rollback() {
+    let mut editor = Editor::from(Rope::from_str("a  bb  c"));
+    editor.replace(1..3, " ").unwrap();
+    editor.insert(8, ";").unwrap();
+
+    let checkpoint = editor.checkpoint();
+    editor.replace(0..3, "A").unwrap();
+    editor.replace(5..7, "\n").unwrap();
+    editor.ignore(7..8);
+    editor.insert(8, ",").unwrap();
+    editor.insert(3, "(").unwrap();
+    assert_eq!("A(bb\nc,", output(&editor));
+
+    editor.rollback(checkpoint);
+    assert_eq!("a bb  c;", output(&editor));
+    assert_eq!(editor.map_byte(8, Bias::Right), 8);
+    editor.insert(7, "C").unwrap();
+    assert_eq!("a bb  Cc;", output(&editor));
+  }
+
+  #[test]
+  fn supersede() {
+    let mut editor = Editor::from(Rope::from_str("a b c"));
+
+    editor.insert(1, ",").unwrap();
+    editor.replace(1..2, "  ").unwrap();
+    assert_eq!("a  b c", output(&editor));
+
+    editor.replace(3..4, "\n").unwrap();
+    editor.insert(3, ";").unwrap();
+    assert_eq!("a  b;\nc", output(&editor));
+
+    editor.replace(0..3, "x").unwrap();
+    assert_eq!("x\nc", output(&editor));
+    assert_eq!(0, editor.map_byte(2, Bias::Left));
+    assert_eq!(1, editor.map_byte(2, Bias::Right));
+    assert_eq!(1, editor.map_byte(3, Bias::Left));
+    assert_eq!(1, editor.map_byte(3, Bias::Right));
+  }
+
+  fn with_src<F>(src: &str, f: F)
+  where
+    F: FnOnce(&Tree, &mut Editor),
+  {
+    let mut parser = Parser::new();
+    parser.set_language(rust_lang()).unwrap();
+    let tree = parser.parse(src, None).unwrap();
+    let mut editor = Editor::from(Rope::from_str(src));
+    f(&tree, &mut editor);
+  }
+
+  /// Replaces the original `range` by `s`, checking that every node located
+  /// after `range` is shifted accordingly.
+  fn test_replace(
+    tree: &Tree,
+    editor: &mut Editor,
+    range: ByteRange<usize>,
+    s: &str,
+    src: &str,
+  ) {
+    let old_src = output(editor);
+    let start_byte = editor.map_byte(range.start, Bias::Left);
+    let end_byte = editor.map_byte(range.end, Bias::Right);
+    let start_point = editor.point(range.start, Bias::Left);
+    let mut cursor = tree.walk();
+    let old_starts = Walker::from(&mut cursor)
+      .map(Node::from)
+      .filter(|node| node.start_byte() >= range.end)
+      .map(|node| (node, editor.map_byte(node.start_byte(), Bias::Right)))
+      .collect::<Vec<_>>();
+
+    editor.replace(range, s).unwrap();
+    assert_eq!(src, output(editor));
+
+    old_starts.into_iter().for_each(|(node, old_start_byte)| {
+      let chars = s.chars().chain(old_src[end_byte..old_start_byte].chars());
+      assert_eq!(
+        old_start_byte - (end_byte - start_byte) + s.len(),
+        editor.map_byte(node.start_byte(), Bias::Right),
+      );
+      assert_eq!(
+        end_point(chars, Some(&start_point)),
+        editor.map_point(&node.start_position(), Bias::Right),
+      );
+    });
+  }
+
+  #[test]
+  fn editor_insert() {
+    with_src("fn f()\n{}", |tree, editor| {
+      let mut test_insert = |byte, s, src| {
+        test_replace(tree, editor, byte..byte, s, src);
+      };
+      test_insert(5, "bar: Bar", "fn f(bar: Bar)\n{}");
+      test_insert(4, "oo", "fn foo(bar: Bar)\n{}");
+      test_insert(8, " baz(); ", "fn foo(bar: Bar)\n{ baz(); }");
+      test_insert(0, "pub\n", "pub\nfn foo(bar: Bar)\n{ baz(); }");
+    });
+  }
+
+  #[test]
+  fn editor_remove() {
+    with_src("pub\nfn foo(bar: Bar)\n{ baz(); }", |tree, editor| {
+      let mut test_remove = |range, src| {
+        test_replace(tree, editor, range, "", src);
+      };
+      test_remove(11..19, "pub\nfn foo()\n{ baz(); }");
+      test_remove(22..30, "pub\nfn foo()\n{}");
+      test_remove(0..4, "fn foo()\n{}");
+      test_remove(8..10, "fn f()\n{}");
+    });
+  }
+
+  #[test]
+  fn editor_replace() {
+    with_src("pub\nfn foo(bar: Bar)\n{ baz(); }", |tree, editor| {
+      let mut test_replace = |range, s, src| {
+        test_replace(tree, editor, range, s, src);
+      };
+      test_replace(11..19, "", "pub\nfn foo()\n{ baz(); }");
+      test_replace(23..29, "\"foo\"", "pub\nfn foo()\n{ \"foo\" }");
+      test_replace(
+        20..21,
+        " -> &'static str ",
+        "pub\nfn foo() -> &'static str { \"foo\" }",
+      );
+      test_replace(0..4, "", "fn foo() -> &'static str { \"foo\" }");
+    });
+  }
+
+  /// Pseudo-random number generator good enough for shuffling edits.
+  struct Lcg(u64);
+
+  impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+      self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1);
+      (self.0 >> 33) as usize % bound
+    }
+  }
+
+  /// Maps `byte` through the `changes` sorted by range, naively.
+  fn naive_map(
+    changes: &[(usize, usize, String)],
+    byte: usize,
+    bias: Bias,
+  ) -> usize {
+    let mut out = byte as isize;
+    for (start, end, s) in changes {
+      let delta = s.len() as isize - (end - start) as isize;
+      if *end < byte || (*end == byte && (start < end || bias == Bias::Right))
+      {
+        out += delta;
+      } else if *start < byte && byte < *end {
+        out += *start as isize - byte as isize;
+        if bias == Bias::Right {
+          out += s.len() as isize;
+        }
+        break;
+      }
+    }
+    out as usize
+  }
+
+  #[test]
+  fn editor_against_naive() {
+    let src = "fn f(a: A) {\n  g(a);\n}\n\nfn g(_: A) {}\n";
+    let mut editor = Editor::from(Rope::from_str(src));
+    let mut naive = Vec::<(usize, usize, String)>::new();
+    let mut out = src.to_string();
+    let mut rng = Lcg(79);
+    let texts = ["", " ", "\n", "xyz", "\n\n  "];
+
+    for _ in 0..256 {
+      let start = rng.next(src.len() + 1);
+      let end = start + rng.next(4).min(src.len() - start);
+      let s = texts[rng.next(texts.len())];
+      editor.replace(start..end, s).unwrap();
+
+      let range = start..end;
+      let out_range = naive_map(&naive, start, Bias::Left)
+        ..naive_map(&naive, end, Bias::Right);
+      if out[out_range] != *s {
+        let (start, end) = naive
+          .iter()
+          .filter(|(a, b, _)| overlaps(&range, (*a, *b)))
+          .fold((start, end), |(start, end), (a, b, _)| {
+            (start.min(*a), end.max(*b))
+          });
+        naive.retain(|(a, b, _)| !overlaps(&range, (*a, *b)));
+        naive.push((start, end, s.to_string()));
+        naive.sort_by_key(|(a, b, _)| (*a, *b));
+      }
+
+      out.clear();
+      let mut byte = 0;
+      naive.iter().for_each(|(a, b, s)| {
+        out.push_str(&src[byte..*a]);
+        out.push_str(s);
+        byte = *b;
+      });
+      out.push_str(&src[byte..]);
+      assert_eq!(out, output(&editor));
+
+      for byte in 0..=src.len() {
+        for bias in [Bias::Left, Bias::Right] {
+          let out_byte = naive_map(&naive, byte, bias);
+          assert_eq!(out_byte, editor.map_byte(byte, bias));
+          let line_start = out[..out_byte].rfind('\n').map_or(0, |ix| ix + 1);
+          assert_eq!(
+            &out[line_start..out_byte],
+            editor.line_prefix(byte, bias)
+          );
+          let point = |s: &str, byte: usize| {
+            let line_start = s[..byte].rfind('\n').map_or(0, |ix| ix + 1);
+            let row = s[..byte].matches('\n').count();
+            Point { row, column: byte - line_start }
+          };
+          assert_eq!(
+            point(&out, out_byte),
+            editor.map_point(&point(src, byte), bias),
+          );
+        }
+
+        let out_range = naive_map(&naive, byte, Bias::Left)
+          ..naive_map(&naive, (byte + 3).min(src.len()), Bias::Right);
+        assert_eq!(
+          &out[out_range],
+          editor.text(byte..(byte + 3).min(src.len())),
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn input_edits() {
+    let src = "fn f(a: A) {\n  g(a);\n}\n\nfn g(_: A) {}\n";
+    let mut parser = Parser::new();
+    parser.set_language(rust_lang()).unwrap();
+    let mut rng = Lcg(97);
+    let texts = ["", " ", "\n", "xyz", "\n\n  "];
+
+    for _ in 0..64 {
+      let mut tree = parser.parse(src, None).unwrap();
+      let mut editor = Editor::from(Rope::from_str(src));
+      for _ in 0..rng.next(8) {
+        let start = rng.next(src.len() + 1);
+        let end = start + rng.next(4).min(src.len() - start);
+        editor.replace(start..end, texts[rng.next(texts.len())]).unwrap();
+      }
+
+      let out = output(&editor);
+      editor
+        .input_edits(RopeSlice::from(out.as_str()))
+        .iter()
+        .for_each(|edit| tree.edit(edit));
+      let incremental = parser.parse(&out, Some(&tree)).unwrap();
+      let fresh = parser.parse(&out, None).unwrap();
+      assert_eq!(
+        incremental.root_node().to_sexp(),
+        fresh.root_node().to_sexp()
+      );
+
+      let mut a = incremental.walk();
+      let mut b = fresh.walk();
+      Walker::from(&mut a).zip(Walker::from(&mut b)).for_each(|(a, b)| {
+        assert_eq!(Node::from(a).range(), Node::from(b).range());
+      });
+    }
+  }
+}
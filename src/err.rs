@@ -1,51 +1,107 @@
 use {
-  std::fmt::Display,
+  crate::{
+    predicates::Error as PredicateErr,
+    registry::Error as RegistryErr,
+    settings::parsers::Error as ParseSettingErr,
+    verify::Error as VerifyErr,
+  },
+  error_stack::Report,
+  std::{fmt::Display, mem},
   tree_sitter::{LanguageError, QueryError},
 };
 
+/// Error of a [`Formatter`].
+///
+/// [`Formatter`]: crate::Formatter
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-  #[error("{0}")]
-  Lang(LanguageError),
+  /// The default setting `key` failed to be parsed.
+  #[error("failed to apply default setting \"{key}\": {cause}")]
+  #[allow(missing_docs)]
+  Default { key: String, cause: ParseSettingErr },
+
+  /// No fixpoint was reached within the given number of passes.
+  #[error("no fixpoint reached after {0} passes")]
+  Fixpoint(usize),
 
-  #[error("language \"{0}\" is unsupported")]
-  LangUnsupported(String),
+  /// Cooking the output again changed its line `line` from `old` to `new`,
+  /// through the predicate `op` from the pattern `pat_ix`.
+  #[error(
+    "cooking the output again changes line {line} through predicate \"{op}\" \
+     from pattern #{pat_ix}:\n-{old}\n+{new}"
+  )]
+  #[allow(missing_docs)]
+  Idempotency {
+    line: usize,
+    old: String,
+    new: String,
+    op: String,
+    pat_ix: usize,
+  },
 
-  #[error("could not detect language")]
-  LangUndetected,
+  /// The language is incompatible with the parser.
+  #[error("{0}")]
+  Lang(LanguageError),
 
-  #[error("failed during execution")]
-  Other,
+  /// The output of the pass `pass` is the same as the one `period` passes
+  /// before.
+  #[error("output of pass #{pass} oscillates with a period of {period}")]
+  #[allow(missing_docs)]
+  Oscillation { pass: usize, period: usize },
 
-  #[error("no input piped")]
-  Pipe,
+  /// The source could not be parsed.
+  #[error("failed to parse source")]
+  Parse,
 
-  #[error("failed to apply predicate \"{op}\" from pattern #{pat_ix}")]
-  Predicate { op: String, pat_ix: usize },
+  /// The predicate `op` from the pattern `pat_ix` failed.
+  #[error(
+    "failed to apply predicate \"{op}\" from pattern #{pat_ix}: {cause}"
+  )]
+  #[allow(missing_docs)]
+  Predicate { op: String, pat_ix: usize, cause: PredicateErr },
 
+  /// The query is invalid.
   #[error("{0}")]
   Query(QueryError),
 
-  #[error("could not open query file")]
-  QueryFile,
+  /// A predicate or setting parser could not be registered.
+  #[error("failed to register: {0}")]
+  Registry(RegistryErr),
 
-  #[error("failed to apply setting \"{key}\" from pattern #{pat_ix}")]
-  Setting { key: String, pat_ix: usize },
+  /// The setting `key` from the pattern `pat_ix` failed to be parsed.
+  #[error(
+    "failed to apply setting \"{key}\" from pattern #{pat_ix}: {cause}"
+  )]
+  #[allow(missing_docs)]
+  Setting { key: String, pat_ix: usize, cause: ParseSettingErr },
 
-  #[error("could not open source file")]
-  SrcFile,
+  /// The output failed verification.
+  #[error("output failed verification: {0}")]
+  Verify(VerifyErr),
 }
 
 impl Error {
-  pub fn lang_unsupported(lang: impl Display) -> Self {
-    Self::LangUnsupported(lang.to_string())
+  pub(crate) fn predicate(
+    op: impl Display,
+    pat_ix: usize,
+    cause: PredicateErr,
+  ) -> Self {
+    Self::Predicate { op: op.to_string(), pat_ix, cause }
   }
 
-  pub fn predicate(op: impl ToString, pat_ix: usize) -> Self {
-    Error::Predicate { op: op.to_string(), pat_ix }
+  pub(crate) fn setting(
+    key: impl Display,
+    pat_ix: usize,
+    cause: ParseSettingErr,
+  ) -> Self {
+    Self::Setting { key: key.to_string(), pat_ix, cause }
   }
+}
 
-  pub fn setting(pat_ix: usize, key: impl ToString) -> Self {
-    Error::Setting { key: key.to_string(), pat_ix }
+impl From<Report<Error>> for Error {
+  fn from(mut report: Report<Error>) -> Self {
+    log::debug!("{report:?}");
+    // The report is dropped right away, so its context can be taken away.
+    mem::replace(report.downcast_mut::<Self>().unwrap(), Self::Parse)
   }
 }
@@ -0,0 +1,217 @@
+use {
+  super::{apply_match, resolve_layout, IgnoreQueries},
+  crate::{
+    editor::{Bias, Editor},
+    err::Error,
+    node_utils::{CapIxToNodes, Displayer, Matches, Provider},
+    predicates::{width, Predicates},
+    settings::{Cpl, Parsers as SettingParsers, Scope, Settings},
+  },
+  error_stack::Result,
+  tree_sitter::{Node, Query},
+};
+
+/// Key of the property naming the candidate layout a pattern belongs to.
+pub(super) const KEY: &str = "alternative";
+
+/// Cost of each column overflowing the maximum number of characters per line.
+const OVERFLOW_COST: usize = 100;
+
+/// Cost of each line break.
+const LINE_COST: usize = 1;
+
+/// Match of a pattern belonging to the candidate layout `name` of `node`,
+/// applied once the candidates are compared.
+pub(super) struct Deferred<'m, 'tree> {
+  pub pat_ix: usize,
+  pub scope: Scope,
+  pub cap_ix_to_nodes: &'m CapIxToNodes<'tree>,
+  pub node: Node<'tree>,
+  pub name: String,
+}
+
+/// Returns the node and the name of the candidate layout the pattern `pat_ix`
+/// belongs to, if any.
+pub(super) fn candidate<'tree>(
+  query: &Query,
+  pat_ix: usize,
+  nodes_provider: &Provider<'_, 'tree>,
+) -> Result<Option<(Node<'tree>, String)>, Error> {
+  use crate::settings::parsers::Error as SettingErr;
+  let prop = match query
+    .property_settings(pat_ix)
+    .iter()
+    .find(|prop| prop.key.as_ref() == KEY)
+  {
+    None => return Ok(None),
+    Some(prop) => prop,
+  };
+  let cap_ix = prop
+    .capture_id
+    .ok_or_else(|| Error::setting(KEY, pat_ix, SettingErr::CapMissing))?;
+  let name = prop.value.as_ref().ok_or_else(|| {
+    Error::setting(KEY, pat_ix, SettingErr::value("candidate", "no value"))
+  })?;
+  let node = nodes_provider
+    .nodes_for_cap_ix(cap_ix as u32)
+    .next()
+    .ok_or_else(|| Error::setting(KEY, pat_ix, SettingErr::NodeMissing))?;
+  Ok(Some((*node, name.to_string())))
+}
+
+/// Returns the cost of the current text of `node` costing `penalty` more,
+/// considering `cpl`.
+fn cost(
+  node: &Node<'_>,
+  cpl: Option<Cpl>,
+  penalty: usize,
+  editor: &Editor,
+) -> usize {
+  let prefix = editor.line_prefix(node.start_byte(), Bias::Left);
+  let text = prefix + &editor.text(node.byte_range());
+  let cpl = match cpl {
+    None | Some(Cpl::Unlimited) => usize::MAX,
+    Some(cpl) => u8::from(cpl) as usize,
+  };
+  let overflow = text
+    .split('\n')
+    .map(|line| width(line).saturating_sub(cpl))
+    .sum::<usize>();
+  let lines = text.matches('\n').count();
+  overflow * OVERFLOW_COST + lines * LINE_COST + penalty
+}
+
+/// Applies to `editor` the deferred matches of the candidate layout `name` of
+/// `node`, and the layout they request.
+#[allow(clippy::too_many_arguments)]
+fn apply_candidate<'a, 'tree>(
+  query: &'a Query,
+  matches: &Matches<'tree>,
+  deferred: &[Deferred<'_, 'tree>],
+  node: &Node<'tree>,
+  name: &str,
+  setting_parsers: &SettingParsers,
+  predicates: &Predicates,
+  ignore_queries: &mut IgnoreQueries,
+  settings: &mut Settings<'a, 'tree>,
+  editor: &mut Editor,
+) -> Result<(), Error> {
+  for deferred in deferred
+    .iter()
+    .filter(|deferred| deferred.node.id() == node.id())
+    .filter(|deferred| deferred.name == name)
+  {
+    let nodes_provider =
+      Provider::new(deferred.cap_ix_to_nodes, matches.id_to_node());
+    apply_match(
+      query,
+      deferred.pat_ix,
+      deferred.scope,
+      &nodes_provider,
+      setting_parsers,
+      predicates,
+      ignore_queries,
+      settings,
+      editor,
+    )?;
+  }
+  if !settings.take_alternatives().is_empty() {
+    log::warn!("nested alternatives of {} ignored", Displayer(node));
+  }
+  resolve_layout(
+    settings.take_layout(),
+    settings,
+    matches.id_to_node(),
+    editor,
+  )
+}
+
+/// Applies to `editor` the cheapest candidate layout of each alternatives
+/// requested into `settings`.
+///
+/// Each candidate is tried speculatively, then rolled back along with the node
+/// settings it changed, so that trying it costs as much as its own edits. The
+/// cheapest one is then applied for good.
+///
+/// Ties are broken in favor of the candidate declared first.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn resolve<'a, 'tree>(
+  query: &'a Query,
+  matches: &Matches<'tree>,
+  deferred: &[Deferred<'_, 'tree>],
+  setting_parsers: &SettingParsers,
+  predicates: &Predicates,
+  ignore_queries: &mut IgnoreQueries,
+  settings: &mut Settings<'a, 'tree>,
+  mut editor: Editor,
+) -> Result<Editor, Error> {
+  let layout = settings.take_layout();
+  let all_alternatives = settings.take_alternatives();
+
+  for deferred in deferred.iter().filter(|deferred| {
+    !all_alternatives.iter().any(|alternatives| {
+      alternatives.node.id() == deferred.node.id()
+        && alternatives.candidates.iter().any(|c| c.name == deferred.name)
+    })
+  }) {
+    log::warn!(
+      "pattern #{} of undeclared candidate \"{}\" of {} ignored",
+      deferred.pat_ix,
+      deferred.name,
+      Displayer(&deferred.node),
+    );
+  }
+
+  for alternatives in all_alternatives {
+    let node = &alternatives.node;
+    let mut best: Option<(usize, &str)> = None;
+
+    for candidate in &alternatives.candidates {
+      let checkpoint = editor.checkpoint();
+      settings.checkpoint_nodes();
+      apply_candidate(
+        query,
+        matches,
+        deferred,
+        node,
+        &candidate.name,
+        setting_parsers,
+        predicates,
+        ignore_queries,
+        settings,
+        &mut editor,
+      )?;
+      let cost = cost(node, alternatives.cpl, candidate.penalty, &editor);
+      editor.rollback(checkpoint);
+      settings.rollback_nodes();
+
+      log::trace!(
+        "candidate \"{}\" of {} costs {cost}",
+        candidate.name,
+        Displayer(node),
+      );
+      if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+        best = Some((cost, &candidate.name));
+      }
+    }
+
+    if let Some((_, name)) = best {
+      log::trace!("chose candidate \"{name}\" of {}", Displayer(node));
+      apply_candidate(
+        query,
+        matches,
+        deferred,
+        node,
+        name,
+        setting_parsers,
+        predicates,
+        ignore_queries,
+        settings,
+        &mut editor,
+      )?;
+    }
+  }
+
+  *settings.layout_mut() = layout;
+  Ok(editor)
+}
@@ -0,0 +1,204 @@
+use {
+  crate::{
+    editor::Editor,
+    node_utils::{Displayer, Walker},
+    settings::Settings,
+  },
+  std::iter,
+  tree_sitter::{Node, Query, Tree},
+};
+
+/// Key of the setting of the prefix of directives.
+const KEY: &str = "directive-prefix";
+
+/// Prefix of directives when none is set.
+const DEFAULT_PREFIX: &str = "cook:";
+
+/// Directive of a comment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Directive {
+  /// Ignores the source up to the next [`On`] directive, or to the end.
+  ///
+  /// [`On`]: Directive::On
+  Off,
+
+  /// Ends an [`Off`] directive.
+  ///
+  /// [`Off`]: Directive::Off
+  On,
+
+  /// Ignores the next named node, other than a comment.
+  SkipNext,
+}
+
+/// Returns the prefix of directives set by a non-rooted pattern of `query`,
+/// if any.
+fn prefix(query: &Query) -> Option<&str> {
+  (0..query.pattern_count())
+    .filter(|pat_ix| !query.is_pattern_rooted(*pat_ix))
+    .flat_map(|pat_ix| query.property_settings(pat_ix).iter())
+    .filter(|prop| prop.key.as_ref() == KEY)
+    .find_map(|prop| prop.value.as_deref())
+}
+
+/// Returns the directive of the comment `text`, if any.
+///
+/// A directive is made of `prefix` followed by `off`, `on` or `skip-next`,
+/// surrounded by the punctuation of the comment only.
+fn directive(text: &str, prefix: &str) -> Option<Directive> {
+  let (opener, rest) = text.split_once(prefix)?;
+  let rest = rest.trim_start();
+  let len = rest
+    .find(|ch: char| !(ch.is_alphanumeric() || ch == '-'))
+    .unwrap_or(rest.len());
+  let (word, closer) = rest.split_at(len);
+  let punctuation = |s: &str| !s.chars().any(char::is_alphanumeric);
+  if !punctuation(opener) || !punctuation(closer) {
+    return None;
+  }
+  match word {
+    "off" => Some(Directive::Off),
+    "on" => Some(Directive::On),
+    "skip-next" => Some(Directive::SkipNext),
+    _ => None,
+  }
+}
+
+#[inline]
+fn is_comment(node: &Node<'_>) -> bool { node.kind().contains("comment") }
+
+/// Ignores the regions of `tree` delimited by the directives of its comments,
+/// whose prefix is set in `query`, or else in the default `settings`.
+pub(super) fn apply<'tree>(
+  tree: &'tree Tree,
+  query: &Query,
+  settings: &mut Settings<'_, 'tree>,
+  editor: &mut Editor,
+) {
+  let prefix = prefix(query)
+    .or_else(|| settings.directive_prefix())
+    .unwrap_or(DEFAULT_PREFIX);
+  let mut off = None;
+  let mut cursor = tree.walk();
+  let comments = Walker::from(&mut cursor)
+    .map(Node::from)
+    .filter(is_comment)
+    .collect::<Vec<_>>();
+
+  for comment in comments {
+    let text = editor.src().byte_slice(comment.byte_range()).to_string();
+    match (directive(&text, prefix), off) {
+      (None, _) => {}
+      (Some(Directive::Off), None) => off = Some(comment.end_byte()),
+      (Some(Directive::On), Some(start)) => {
+        log::trace!(
+          "ignoring bytes {start}..{} by directive",
+          comment.start_byte()
+        );
+        editor.ignore(start..comment.start_byte());
+        off = None;
+      }
+      (Some(Directive::SkipNext), None) => {
+        match iter::successors(
+          comment.next_named_sibling(),
+          Node::next_named_sibling,
+        )
+        .find(|node| !is_comment(node))
+        {
+          None => log::warn!("nothing to skip after {}", Displayer(&comment)),
+          Some(node) => {
+            log::trace!("ignoring {} by directive", Displayer(&node));
+            settings.ignore(&node);
+          }
+        }
+      }
+      (Some(directive), _) => {
+        log::warn!(
+          "unexpected {directive:?} directive at {}",
+          Displayer(&comment)
+        );
+      }
+    }
+  }
+
+  if let Some(start) = off {
+    log::trace!("ignoring bytes {start}.. by directive");
+    editor.ignore(start..editor.src().len_bytes());
+  }
+  settings
+    .take_ignored()
+    .into_iter()
+    .for_each(|node| editor.ignore(node.byte_range()));
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{query_testing::prelude::*, Formatter},
+  };
+
+  #[test]
+  fn directive_() {
+    let test = |text, directive_| {
+      assert_eq!(directive(text, DEFAULT_PREFIX), directive_)
+    };
+    test("// cook: off", Some(Directive::Off));
+    test("/* cook:on */", Some(Directive::On));
+    test("# cook: skip-next", Some(Directive::SkipNext));
+    test("// cook: off because", None);
+    test("// see cook: off", None);
+    test("// cook: of", None);
+  }
+
+  #[test]
+  fn directives() {
+    let query = lines!(
+      r#"(#set! directive-prefix "fmt:")"#,
+      "((source_file) @file (#spacer! @file))",
+    );
+    let src = lines!(
+      "fn  f() {}",
+      "// fmt: off",
+      "fn  g() {}",
+      "// fmt: on",
+      "fn  h() {",
+      "  // fmt: skip-next",
+      "  let  x  =  [",
+      "    1,  2,",
+      "  ];",
+      "  let  y  =  1;",
+      "}",
+      "/* fmt: off */",
+      "fn  i() {}",
+    );
+    let res = cook_with(src, query, |_| {}, Options::default());
+    assert_eq!(
+      res.unwrap(),
+      lines!(
+        "fn f ( ) { }",
+        "// fmt: off",
+        "fn  g() {}",
+        "// fmt: on",
+        "fn h ( ) {",
+        "  // fmt: skip-next",
+        "  let  x  =  [",
+        "    1,  2,",
+        "  ]; let y = 1 ; }",
+        "/* fmt: off */",
+        "fn  i() {}",
+      ),
+    );
+  }
+
+  #[test]
+  fn directives_default() {
+    let query = "((source_file) @file (#spacer! @file))";
+    let mut formatter = Formatter::new(rs_lang(), query).unwrap();
+    formatter.set_default("directive-prefix", "fmt:").unwrap();
+    assert_eq!(
+      formatter.format("fn  f() {}\n// fmt: off\nfn  g() {}\n").unwrap(),
+      "fn f ( ) { }\n// fmt: off\nfn  g() {}\n",
+    );
+  }
+}
@@ -0,0 +1,820 @@
+mod alternatives;
+mod directives;
+
+use {
+  crate::{
+    editor::{Change, Editor, Origin, Policy},
+    err::Error,
+    node_utils::{Displayer, IdToNode, Matches, Provider},
+    predicates::{Error as PredicateErr, Layout, Predicate, Predicates},
+    settings::{
+      parsers::{Error as ParseSettingErr, Parser as SettingParser},
+      Parsers as SettingParsers,
+      Scope,
+      Settings,
+    },
+    verify,
+  },
+  error_stack::{bail, IntoReport, Result},
+  fnv::FnvHashMap,
+  ropey::{iter::Chunks, Rope, RopeSlice},
+  tree_sitter::{
+    Language,
+    Node,
+    Parser,
+    Query,
+    QueryCursor,
+    QueryProperty,
+    TextProvider,
+    Tree,
+  },
+};
+
+pub(crate) fn parse_rope_slice(
+  src: RopeSlice<'_>,
+  parser: &mut Parser,
+  old_tree: Option<&Tree>,
+) -> Result<Tree, Error> {
+  parser
+    .parse_with(
+      &mut |byte_ix, _| match byte_ix < src.len_bytes() {
+        false => "",
+        true => {
+          let (s, chunk_byte_ix, ..) = src.chunk_at_byte(byte_ix);
+          &s[byte_ix - chunk_byte_ix..]
+        }
+      },
+      old_tree,
+    )
+    .ok_or(Error::Parse)
+    .report()
+}
+
+struct ChunksBytes<'a>(Chunks<'a>);
+
+impl<'a> Iterator for ChunksBytes<'a> {
+  type Item = &'a [u8];
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> { self.0.next().map(str::as_bytes) }
+}
+
+#[derive(Clone)]
+struct RopeProvider<'a>(RopeSlice<'a>);
+
+impl<'a> TextProvider<'a> for RopeProvider<'a> {
+  type I = ChunksBytes<'a>;
+
+  #[inline]
+  fn text(&mut self, node: Node<'_>) -> Self::I {
+    ChunksBytes(self.0.byte_slice(node.byte_range()).chunks())
+  }
+}
+
+/// Options of a [`Formatter`].
+#[derive(Clone, Copy, Debug)]
+pub struct Options {
+  /// Policy resolving overlapping edits.
+  pub policy: Policy,
+
+  /// Maximum number of passes to reach a fixpoint with, if any.
+  ///
+  /// Without it, the source is cooked once.
+  pub max_passes: Option<usize>,
+
+  /// Whether to check that the output holds the same tokens as the source,
+  /// and no new syntax error.
+  pub verify: bool,
+
+  /// Whether to check that cooking the output leaves it as is.
+  pub check_idempotent: bool,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Self {
+      policy: Policy::default(),
+      max_passes: None,
+      verify: true,
+      check_idempotent: false,
+    }
+  }
+}
+
+/// Queries of the "ignore-query" settings by source, compiled once per cook.
+struct IgnoreQueries {
+  queries: FnvHashMap<String, Query>,
+  query_cursor: QueryCursor,
+}
+
+impl Default for IgnoreQueries {
+  fn default() -> Self {
+    Self { queries: FnvHashMap::default(), query_cursor: QueryCursor::new() }
+  }
+}
+
+/// Ignores the nodes captured by the "ignore-query" setting, if any, within
+/// the nodes of the current match.
+fn ignore_matches<'tree>(
+  pat_ix: usize,
+  nodes_provider: &Provider<'_, 'tree>,
+  ignore_queries: &mut IgnoreQueries,
+  settings: &mut Settings<'_, 'tree>,
+  editor: &Editor,
+) -> Result<(), Error> {
+  const KEY: &str = "ignore-query";
+  let (query_src, node) =
+    match (settings.ignore_query(), nodes_provider.nodes_for_match().next()) {
+      (Some(query_src), Some(node)) => (query_src, *node),
+      _ => return Ok(()),
+    };
+
+  let IgnoreQueries { queries, query_cursor } = ignore_queries;
+  if !queries.contains_key(query_src) {
+    let query = Query::new(node.language(), query_src).map_err(|err| {
+      let cause = ParseSettingErr::value("valid query", err.message);
+      Error::setting(KEY, pat_ix, cause)
+    })?;
+    queries.insert(query_src.to_string(), query);
+  }
+  let query = &queries[query_src];
+  for node in nodes_provider.nodes_for_match() {
+    let matches =
+      query_cursor.matches(query, *node, RopeProvider(editor.src()));
+    for query_match in matches {
+      query_match.captures.iter().for_each(|capture| {
+        log::trace!(
+          "ignoring {} matched by \"{KEY}\"",
+          Displayer(&capture.node)
+        );
+        settings.ignore(&capture.node);
+      });
+    }
+  }
+  Ok(())
+}
+
+/// Applies the default global settings `defaults`.
+fn apply_defaults<'a>(
+  defaults: &'a [QueryProperty],
+  setting_parsers: &SettingParsers,
+  settings: &mut Settings<'a, '_>,
+) -> Result<(), Error> {
+  let (cap_ix_to_nodes, id_to_node) = Default::default();
+  let nodes_provider = Provider::new(&cap_ix_to_nodes, &id_to_node);
+  for prop in defaults {
+    setting_parsers
+      .parse(prop, Scope::Global, &nodes_provider, settings)
+      .map_err(|report| {
+        let cause = report.current_context().clone();
+        report
+          .change_context(Error::Default { key: prop.key.to_string(), cause })
+      })?;
+  }
+  Ok(())
+}
+
+/// Applies the settings and the predicates of the pattern `pat_ix` to the
+/// nodes of one of its matches.
+#[allow(clippy::too_many_arguments)]
+fn apply_match<'a, 'tree>(
+  query: &'a Query,
+  pat_ix: usize,
+  scope: Scope,
+  nodes_provider: &Provider<'_, 'tree>,
+  setting_parsers: &SettingParsers,
+  predicates: &Predicates,
+  ignore_queries: &mut IgnoreQueries,
+  settings: &mut Settings<'a, 'tree>,
+  editor: &mut Editor,
+) -> Result<(), Error> {
+  for query_prop in query.property_settings(pat_ix).iter() {
+    let key = query_prop.key.as_ref();
+    if key == alternatives::KEY {
+      continue;
+    }
+    setting_parsers
+      .parse(query_prop, scope, nodes_provider, settings)
+      .map_err(|report| {
+        let cause = report.current_context().clone();
+        report.change_context(Error::setting(key, pat_ix, cause))
+      })?;
+  }
+  ignore_matches(pat_ix, nodes_provider, ignore_queries, settings, editor)?;
+  settings
+    .take_ignored()
+    .into_iter()
+    .for_each(|node| editor.ignore(node.byte_range()));
+
+  for (pred_ix, query_predicate) in
+    query.general_predicates(pat_ix).iter().enumerate()
+  {
+    let op = query_predicate.operator.as_ref();
+    editor.set_origin(Some(Origin { pat_ix, pred_ix }));
+    predicates
+      .parse(query, query_predicate, scope, nodes_provider, settings, editor)
+      .map_err(|report| {
+        let cause = report.current_context().clone();
+        report.change_context(Error::predicate(op, pat_ix, cause))
+      })?;
+  }
+
+  editor.set_origin(None);
+  settings.reset();
+  Ok(())
+}
+
+/// Breaks the soft line breaks of `layout` into `editor`.
+fn resolve_layout<'tree>(
+  layout: Layout<'tree>,
+  settings: &Settings<'_, 'tree>,
+  id_to_node: &IdToNode<'tree>,
+  editor: &mut Editor,
+) -> Result<(), Error> {
+  layout.resolve(settings, id_to_node, editor).map_err(|(origin, err)| {
+    let pat_ix = origin.map_or(0, |origin| origin.pat_ix);
+    Error::predicate("softline!", pat_ix, PredicateErr::Edit(err))
+  })?;
+  Ok(())
+}
+
+/// Applies the patterns of `query` to `tree`, parsed from `src`, returning
+/// the editor holding the resulting edits.
+#[allow(clippy::too_many_arguments)]
+fn cook_pass(
+  tree: &Tree,
+  src: RopeSlice<'_>,
+  query: &Query,
+  query_cursor: &mut QueryCursor,
+  setting_parsers: &SettingParsers,
+  defaults: &[QueryProperty],
+  predicates: &Predicates,
+  ignore_queries: &mut IgnoreQueries,
+  policy: Policy,
+) -> Result<Editor, Error> {
+  let mut editor = Editor::new(Rope::from(src), policy);
+  let mut settings = Settings::default();
+  let mut deferred = Vec::new();
+  apply_defaults(defaults, setting_parsers, &mut settings)?;
+  directives::apply(tree, query, &mut settings, &mut editor);
+
+  let matches = Matches::from(query_cursor.matches(
+    query,
+    tree.root_node(),
+    RopeProvider(src),
+  ));
+
+  for (pat_ix, cap_ix_to_nodes_slice) in matches.iter() {
+    log::trace!("applying pattern #{pat_ix}");
+
+    let is_pat_rooted = query.is_pattern_rooted(pat_ix);
+    let scope = match is_pat_rooted {
+      false => Scope::Global,
+      true => Scope::Local,
+    };
+
+    for cap_ix_to_nodes in cap_ix_to_nodes_slice {
+      let nodes_provider =
+        Provider::new(cap_ix_to_nodes, matches.id_to_node());
+      match alternatives::candidate(query, pat_ix, &nodes_provider)? {
+        Some((node, name)) => deferred.push(alternatives::Deferred {
+          pat_ix,
+          scope,
+          cap_ix_to_nodes,
+          node,
+          name,
+        }),
+        None => apply_match(
+          query,
+          pat_ix,
+          scope,
+          &nodes_provider,
+          setting_parsers,
+          predicates,
+          ignore_queries,
+          &mut settings,
+          &mut editor,
+        )?,
+      }
+
+      if !is_pat_rooted {
+        log::trace!("skipping redundant non-rooted matches");
+        break;
+      }
+    }
+  }
+
+  let mut editor = alternatives::resolve(
+    query,
+    &matches,
+    &deferred,
+    setting_parsers,
+    predicates,
+    ignore_queries,
+    &mut settings,
+    editor,
+  )?;
+  resolve_layout(
+    settings.take_layout(),
+    &settings,
+    matches.id_to_node(),
+    &mut editor,
+  )?;
+  Ok(editor)
+}
+
+/// Returns the first line differing between `a` and `b`, if any.
+fn first_diff_line(a: RopeSlice<'_>, b: RopeSlice<'_>) -> Option<usize> {
+  let mut a_lines = a.lines();
+  let mut b_lines = b.lines();
+  (0..).find_map(|ix| match (a_lines.next(), b_lines.next()) {
+    (None, None) => Some(None),
+    (a_line, b_line) if a_line != b_line => Some(Some(ix)),
+    _ => None,
+  })?
+}
+
+/// Checks that cooking `out`, whose tree is `tree`, leaves it as is.
+///
+/// Otherwise, the first differing line is reported along with the pattern and
+/// the predicate from which the edit changing it was made.
+#[allow(clippy::too_many_arguments)]
+fn check_idempotent(
+  tree: &Tree,
+  out: RopeSlice<'_>,
+  query: &Query,
+  query_cursor: &mut QueryCursor,
+  setting_parsers: &SettingParsers,
+  defaults: &[QueryProperty],
+  predicates: &Predicates,
+  ignore_queries: &mut IgnoreQueries,
+  policy: Policy,
+) -> Result<(), Error> {
+  let editor = cook_pass(
+    tree,
+    out,
+    query,
+    query_cursor,
+    setting_parsers,
+    defaults,
+    predicates,
+    ignore_queries,
+    policy,
+  )?;
+  let changes = editor.changes();
+  let new_out = changes.apply();
+  let row = match first_diff_line(out, new_out.slice(..)) {
+    None => return Ok(()),
+    Some(row) => row,
+  };
+
+  let line_start = out.line_to_byte(row.min(out.len_lines() - 1));
+  let origin = changes
+    .iter()
+    .find(|Change { range, .. }| range.end >= line_start)
+    .and_then(|change| change.origin);
+  let (op, pat_ix) = match origin {
+    None => ("?".to_string(), 0),
+    Some(Origin { pat_ix, pred_ix }) => {
+      (query.general_predicates(pat_ix)[pred_ix].operator.to_string(), pat_ix)
+    }
+  };
+  let line = |src: RopeSlice<'_>| match row < src.len_lines() {
+    false => String::new(),
+    true => src.line(row).to_string().trim_end_matches('\n').to_string(),
+  };
+
+  bail!(Error::Idempotency {
+    line: row + 1,
+    old: line(out),
+    new: line(new_out.slice(..)),
+    op,
+    pat_ix,
+  })
+}
+
+/// Cooks `src` once, or, when `opts.max_passes` is given, repeatedly until the
+/// output stops changing.
+///
+/// Between passes, the tree is edited and incrementally reparsed, so that
+/// every pass works on nodes up to date with the output of the previous one.
+/// Failing to reach a fixpoint within `max_passes` is an error, and so is an
+/// output coming back to a prior one.
+///
+/// The output is then optionally checked with [`verify`] and
+/// [`check_idempotent`].
+///
+/// [`verify`]: verify::verify
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn cook(
+  parser: &mut Parser,
+  src: RopeSlice<'_>,
+  lang: Language,
+  query: &Query,
+  query_cursor: &mut QueryCursor,
+  setting_parsers: &SettingParsers,
+  defaults: &[QueryProperty],
+  predicates: &Predicates,
+  opts: Options,
+) -> Result<Rope, Error> {
+  let Options { policy, max_passes, .. } = opts;
+  parser.set_language(lang).map_err(Error::Lang)?;
+  let src_tree = parse_rope_slice(src, parser, None)?;
+  let mut tree = src_tree.clone();
+  let mut outputs = vec![Rope::from(src)];
+  let mut ignore_queries = IgnoreQueries::default();
+
+  let out = 'passes: {
+    for pass in 1..=max_passes.unwrap_or(1) {
+      log::trace!("cooking pass #{pass}");
+      let src = outputs.last().unwrap().slice(..);
+      let editor = cook_pass(
+        &tree,
+        src,
+        query,
+        query_cursor,
+        setting_parsers,
+        defaults,
+        predicates,
+        &mut ignore_queries,
+        policy,
+      )?;
+
+      let changes = editor.changes();
+      if changes.is_empty() {
+        log::trace!("fixpoint reached after {pass} passes");
+        break 'passes outputs.pop().unwrap();
+      }
+
+      let out = changes.apply();
+      editor
+        .input_edits(out.slice(..))
+        .iter()
+        .for_each(|edit| tree.edit(edit));
+      tree = parse_rope_slice(out.slice(..), parser, Some(&tree))?;
+      if max_passes.is_none() {
+        break 'passes out;
+      }
+      if let Some(ix) = outputs.iter().position(|prev| *prev == out) {
+        match ix + 1 == outputs.len() {
+          false => {
+            bail!(Error::Oscillation { pass, period: outputs.len() - ix })
+          }
+          true => {
+            log::trace!("fixpoint reached after {pass} passes");
+            break 'passes out;
+          }
+        }
+      }
+      outputs.push(out);
+    }
+
+    bail!(Error::Fixpoint(max_passes.unwrap_or(1)))
+  };
+
+  if opts.verify {
+    verify::verify(&src_tree, src, &tree, out.slice(..)).map_err(
+      |report| {
+        let cause = report.current_context().clone();
+        report.change_context(Error::Verify(cause))
+      },
+    )?;
+  }
+
+  if opts.check_idempotent {
+    check_idempotent(
+      &tree,
+      out.slice(..),
+      query,
+      query_cursor,
+      setting_parsers,
+      defaults,
+      predicates,
+      &mut ignore_queries,
+      policy,
+    )?;
+  }
+
+  Ok(out)
+}
+
+/// Formatter of sources of a language, according to a query.
+///
+/// The query is made of patterns whose settings and predicates describe how
+/// the nodes they capture are formatted. Predicates and setting parsers other
+/// than the builtin ones may be registered.
+pub struct Formatter {
+  lang: Language,
+  query: Query,
+  defaults: Vec<QueryProperty>,
+  setting_parsers: SettingParsers,
+  predicates: Predicates,
+  opts: Options,
+}
+
+impl Formatter {
+  /// Creates a formatter of `lang` sources from `query_src`, with the builtin
+  /// predicates and setting parsers.
+  pub fn new(
+    lang: Language,
+    query_src: &str,
+  ) -> std::result::Result<Self, Error> {
+    Parser::new().set_language(lang).map_err(Error::Lang)?;
+    Ok(Self {
+      lang,
+      query: Query::new(lang, query_src).map_err(Error::Query)?,
+      defaults: Vec::new(),
+      setting_parsers: SettingParsers::builtin().map_err(Error::Registry)?,
+      predicates: Predicates::builtin().map_err(Error::Registry)?,
+      opts: Options::default(),
+    })
+  }
+
+  /// Sets the options of the formatter.
+  #[inline]
+  pub fn with_options(mut self, opts: Options) -> Self {
+    self.opts = opts;
+    self
+  }
+
+  /// Returns the options of the formatter.
+  #[inline]
+  pub fn options(&self) -> &Options { &self.opts }
+
+  /// Returns the options of the formatter, mutably.
+  #[inline]
+  pub fn options_mut(&mut self) -> &mut Options { &mut self.opts }
+
+  /// Registers `predicate` besides the builtin ones.
+  #[inline]
+  pub fn register_predicate(
+    &mut self,
+    predicate: impl Predicate + 'static,
+  ) -> std::result::Result<(), Error> {
+    self.predicates.push(predicate).map_err(Error::Registry)
+  }
+
+  /// Registers the setting parser `parser` besides the builtin ones.
+  #[inline]
+  pub fn register_parser(
+    &mut self,
+    parser: impl SettingParser + 'static,
+  ) -> std::result::Result<(), Error> {
+    self.setting_parsers.push(parser).map_err(Error::Registry)
+  }
+
+  /// Disables the predicate `name`, leaving it without effect, and returns
+  /// whether it was registered.
+  #[inline]
+  pub fn disable_predicate(&mut self, name: &str) -> bool {
+    self.predicates.disable(name)
+  }
+
+  /// Sets the global setting `key` to `value` by default, before the query
+  /// applies.
+  pub fn set_default(
+    &mut self,
+    key: &str,
+    value: &str,
+  ) -> std::result::Result<(), Error> {
+    let prop = QueryProperty::new(key, Some(value), None);
+    apply_defaults(
+      std::slice::from_ref(&prop),
+      &self.setting_parsers,
+      &mut Settings::default(),
+    )?;
+    self.defaults.push(prop);
+    Ok(())
+  }
+
+  /// Returns the registered predicates.
+  #[inline]
+  pub fn predicates(&self) -> &Predicates { &self.predicates }
+
+  /// Returns the registered setting parsers.
+  #[inline]
+  pub fn setting_parsers(&self) -> &SettingParsers { &self.setting_parsers }
+
+  /// Formats `src`.
+  pub fn format(&self, src: &str) -> std::result::Result<String, Error> {
+    self.format_rope_slice(RopeSlice::from(src)).map(String::from)
+  }
+
+  /// Formats the rope `src`.
+  #[inline]
+  pub fn format_rope(&self, src: &Rope) -> std::result::Result<Rope, Error> {
+    self.format_rope_slice(src.slice(..))
+  }
+
+  /// Formats the rope slice `src`.
+  pub fn format_rope_slice(
+    &self,
+    src: RopeSlice<'_>,
+  ) -> std::result::Result<Rope, Error> {
+    cook(
+      &mut Parser::new(),
+      src,
+      self.lang,
+      &self.query,
+      &mut QueryCursor::new(),
+      &self.setting_parsers,
+      &self.defaults,
+      &self.predicates,
+      self.opts,
+    )
+    .map_err(Error::from)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      predicates::Error as PredicateErr,
+      query_testing::prelude::*,
+      registry::Error as RegistryErr,
+    },
+    tree_sitter::QueryPredicateArg,
+  };
+
+  /// Prepends `"x"` to the source until it starts with `"xxx"`.
+  struct Pad;
+
+  impl Predicate for Pad {
+    fn name(&self) -> &'static str { "pad!" }
+
+    fn parse<'a, 'tree>(
+      &self,
+      _query: &Query,
+      _args: &'a [QueryPredicateArg],
+      _scope: Scope,
+      _nodes_provider: &Provider<'_, 'tree>,
+      _settings: &mut Settings<'a, 'tree>,
+      editor: &mut Editor,
+    ) -> Result<(), PredicateErr> {
+      match editor.src().bytes().take(3).all(|b| b == b'x') {
+        false => editor.insert(0, "x").map_err(PredicateErr::Edit).report(),
+        true => Ok(()),
+      }
+    }
+  }
+
+  /// Prepends `"x"` to the source, or removes it when already there.
+  struct Toggle;
+
+  impl Predicate for Toggle {
+    fn name(&self) -> &'static str { "toggle!" }
+
+    fn parse<'a, 'tree>(
+      &self,
+      _query: &Query,
+      _args: &'a [QueryPredicateArg],
+      _scope: Scope,
+      _nodes_provider: &Provider<'_, 'tree>,
+      _settings: &mut Settings<'a, 'tree>,
+      editor: &mut Editor,
+    ) -> Result<(), PredicateErr> {
+      let res = match editor.src().byte(0) {
+        b'x' => editor.remove(0..1),
+        _ => editor.insert(0, "x"),
+      };
+      res.map_err(PredicateErr::Edit).report()
+    }
+  }
+
+  #[test]
+  fn fixpoint() {
+    let query_src = lines!(
+      r#"(#set! indent-style "  ")"#,
+      "((block (_) @stmt)",
+      r#" (#set! @stmt indent-rule "+1")"#,
+      " (#indent! @stmt))",
+    );
+    let opts = Options { max_passes: Some(4), ..Default::default() };
+    let res = cook_with("fn f() {{g();}}", query_src, |_| {}, opts);
+    assert_eq!(res.unwrap(), "fn f() {\n  {\n    g();}}");
+  }
+
+  fn cook_pad(opts: Options) -> Result<Rope, Error> {
+    cook_with(
+      "fn f() {}",
+      "(source_file)\n((source_file) (#pad!))",
+      |predicates| {
+        predicates.push(Pad).unwrap();
+      },
+      opts,
+    )
+  }
+
+  #[test]
+  fn formatter() {
+    let mut formatter =
+      Formatter::new(rs_lang(), "((source_file) (#pad!))").unwrap();
+    assert!(matches!(
+      formatter.format("fn f() {}"),
+      Err(Error::Predicate { pat_ix: 0, cause: PredicateErr::Op(op), .. })
+        if op == "pad!",
+    ));
+
+    formatter.register_predicate(Pad).unwrap();
+    assert!(matches!(
+      formatter.register_predicate(Pad),
+      Err(Error::Registry(RegistryErr::Collision(name))) if name == "pad!",
+    ));
+    assert!(matches!(formatter.format("fn f() {}"), Err(Error::Verify(_)),));
+
+    formatter.options_mut().verify = false;
+    assert_eq!(formatter.format("fn f() {}").unwrap(), "xfn f() {}");
+
+    assert!(matches!(
+      Formatter::new(rs_lang(), "((source_file) (#spacer! \"x\"))")
+        .unwrap()
+        .format("fn f() {}"),
+      Err(Error::Predicate { pat_ix: 0, cause: PredicateErr::Arg { .. }, .. }),
+    ));
+    assert!(matches!(
+      Formatter::new(rs_lang(), "((source_file) (#pad!)"),
+      Err(Error::Query(_)),
+    ));
+  }
+
+  #[test]
+  fn defaults() {
+    let mut formatter = Formatter::new(
+      rs_lang(),
+      lines!(
+        "((block (expression_statement) @stmt)",
+        r#" (#set! @stmt indent-rule "+1")"#,
+        " (#indent! @stmt))",
+        "((expression_statement) @stmt (#spacer! @stmt))",
+      ),
+    )
+    .unwrap();
+    assert!(matches!(formatter.set_default("indent-style", "\t"), Ok(()),));
+    assert!(matches!(
+      formatter.set_default("cpl", "x"),
+      Err(Error::Default { key, .. }) if key == "cpl",
+    ));
+    assert_eq!(
+      formatter.format("fn f() {\n g( );\n}").unwrap(),
+      "fn f() {\n\tg ( ) ;\n}"
+    );
+
+    assert!(formatter.disable_predicate("spacer!"));
+    assert!(!formatter.disable_predicate("pad!"));
+    assert_eq!(
+      formatter.format("fn f() {\n g( );\n}").unwrap(),
+      "fn f() {\n\tg( );\n}"
+    );
+  }
+
+  #[test]
+  fn idempotency() {
+    let opts =
+      Options { verify: false, check_idempotent: true, ..Default::default() };
+    match cook_pad(opts).unwrap_err().current_context() {
+      Error::Idempotency { line, old, new, op, pat_ix } => {
+        assert_eq!(
+          (*line, old.as_str(), new.as_str(), op.as_str(), *pat_ix),
+          (1, "xfn f() {}", "xxfn f() {}", "pad!", 1),
+        );
+      }
+      err => panic!("unexpected error {err:?}"),
+    }
+    let opts = Options { max_passes: Some(4), ..opts };
+    assert_eq!(cook_pad(opts).unwrap(), "xxxfn f() {}");
+  }
+
+  #[test]
+  fn passes() {
+    let cook_pad = |max_passes| {
+      cook_pad(Options {
+        max_passes: Some(max_passes),
+        verify: false,
+        ..Default::default()
+      })
+    };
+    assert_eq!(cook_pad(4).unwrap(), "xxxfn f() {}");
+    assert!(matches!(
+      cook_pad(3).unwrap_err().current_context(),
+      Error::Fixpoint(3),
+    ));
+  }
+
+  #[test]
+  fn oscillation() {
+    let res = cook_with(
+      "fn f() {}",
+      "((source_file) (#toggle!))",
+      |predicates| {
+        predicates.push(Toggle).unwrap();
+      },
+      Options { max_passes: Some(4), verify: false, ..Default::default() },
+    );
+    assert!(matches!(
+      res.unwrap_err().current_context(),
+      Error::Oscillation { pass: 2, period: 2 },
+    ));
+  }
+}
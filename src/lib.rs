@@ -0,0 +1,56 @@
+//! Experimental multilingual code formatter based on [Tree-Sitter]'s [query].
+//!
+//! Sources are formatted by a [`Formatter`], built from a [`Language`] and
+//! the source of a query whose patterns describe how to format the nodes they
+//! capture:
+//!
+//! ```
+//! use witch_cooking::Formatter;
+//!
+//! let query_src = "((source_file) @root (#spacer! @root))";
+//! let formatter = Formatter::new(tree_sitter_rust::language(), query_src)?;
+//! assert_eq!(formatter.format("fn f(){}")?, "fn f ( ) { }");
+//! # Ok::<(), witch_cooking::Error>(())
+//! ```
+//!
+//! [Tree-Sitter]: https://tree-sitter.github.io/tree-sitter
+//! [query]: https://tree-sitter.github.io/tree-sitter/using-parsers#query-syntax
+//! [`Language`]: tree_sitter::Language
+
+#![deny(elided_lifetimes_in_paths, missing_docs)]
+
+#[cfg(not(any(
+  feature = "bash",
+  feature = "c",
+  feature = "cpp",
+  feature = "go",
+  feature = "html",
+  feature = "java",
+  feature = "javascript",
+  feature = "python",
+  feature = "rust",
+  feature = "toml",
+)))]
+compile_error!("no language to support");
+
+/// Deferred edits of a source.
+pub mod editor;
+mod err;
+mod formatter;
+/// Utilities around nodes.
+pub mod node_utils;
+/// Predicates editing the source.
+pub mod predicates;
+#[cfg(test)]
+mod query_testing;
+/// Registries of predicates and setting parsers.
+pub mod registry;
+/// Settings set by the query.
+pub mod settings;
+/// Verification of the output.
+pub mod verify;
+
+pub use {
+  err::Error,
+  formatter::{Formatter, Options},
+};
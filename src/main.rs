@@ -3,66 +3,51 @@
 //! [Tree-Sitter]: https://tree-sitter.github.io/tree-sitter
 //! [query]: https://tree-sitter.github.io/tree-sitter/using-parsers#query-syntax
 
-#![allow(dead_code)]
 #![deny(elided_lifetimes_in_paths, missing_docs)]
 
-#[cfg(not(any(
-  feature = "bash",
-  feature = "c",
-  feature = "cpp",
-  feature = "go",
-  feature = "html",
-  feature = "java",
-  feature = "javascript",
-  feature = "markdown",
-  feature = "ocaml",
-  feature = "python",
-  feature = "rust",
-  feature = "svelte",
-  feature = "toml",
-)))]
-compile_error!("no language to support");
-
 mod cli;
-mod editor;
-mod err;
-mod node_utils;
-mod predicates;
-#[cfg(test)]
-mod query_testing;
-mod settings;
 
 use {
-  self::{
-    cli::Opts,
-    editor::Editor,
-    err::Error,
-    node_utils::{Matches, Provider},
-    predicates::{Error as PredicateErr, Predicates},
-    settings::{
-      parsers::Error as ParseSettingErr,
-      Parsers as SettingParsers,
-      Scope,
-      Settings,
-    },
+  self::cli::{
+    files_from,
+    walk,
+    Config,
+    Configs,
+    Error,
+    Filter,
+    Lang,
+    Opts,
+    Outcome,
+    Summary,
   },
   error_stack::{bail, IntoReport, Result, ResultExt},
-  ropey::{iter::Chunks, Rope, RopeSlice},
-  std::{fs, io, process::ExitCode},
-  tree_sitter::{
-    Language,
-    Node,
-    Parser,
-    Query,
-    QueryCursor,
-    TextProvider,
-    Tree,
+  fnv::FnvHashMap,
+  ropey::Rope,
+  std::{
+    collections::hash_map::Entry,
+    env,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+  },
+  witch_cooking::{
+    predicates::Predicates,
+    settings::Parsers,
+    Formatter,
+    Options,
   },
 };
 
+/// Exit code of a check finding sources to change.
+const EXIT_CHANGED: u8 = 1;
+
+/// Exit code of a check or a write failing.
+const EXIT_ERROR: u8 = 2;
+
 #[inline]
-fn src_rope_from_opts(opts: &Opts) -> Result<Rope, Error> {
-  Ok(match &opts.src {
+fn src_rope(path: Option<&Path>) -> Result<Rope, Error> {
+  Ok(match path {
     None => match atty::isnt(atty::Stream::Stdin) {
       false => bail!(Error::Pipe),
       true => Rope::from_reader(io::BufReader::new(io::stdin()))
@@ -71,7 +56,10 @@ fn src_rope_from_opts(opts: &Opts) -> Result<Rope, Error> {
         .change_context(Error::Other)?,
     },
     Some(path) => Rope::from_reader(io::BufReader::new(
-      fs::File::open(path).report().change_context(Error::SrcFile)?,
+      fs::File::open(path)
+        .report()
+        .attach_printable_lazy(|| path.display().to_string())
+        .change_context(Error::SrcFile)?,
     ))
     .report()
     .attach_printable("failed to create rope")
@@ -80,9 +68,13 @@ fn src_rope_from_opts(opts: &Opts) -> Result<Rope, Error> {
 }
 
 #[inline]
-fn ts_lang_from_opts(opts: &Opts) -> Result<Language, Error> {
-  Ok(Language::from(match opts.lang {
-    None => match opts.src.as_ref().and_then(detect_lang::from_path) {
+fn lang(
+  opts: &Opts,
+  config: &Config,
+  path: Option<&Path>,
+) -> Result<Lang, Error> {
+  Ok(match opts.lang.or_else(|| path.and_then(|path| config.lang(path))) {
+    None => match path.and_then(detect_lang::from_path) {
       Some(lang) => {
         log::info!("auto detected {} language", lang.name());
         lang.try_into().report()?
@@ -90,159 +82,248 @@ fn ts_lang_from_opts(opts: &Opts) -> Result<Language, Error> {
       _ => bail!(Error::LangUndetected),
     },
     Some(lang) => lang,
-  }))
+  })
 }
 
-#[inline]
-fn parse_rope_slice(
-  src: RopeSlice<'_>,
-  parser: &mut Parser,
-) -> Result<Tree, Error> {
-  parser
-    .parse_with(
-      &mut |byte_ix, _| {
-        let (s, chunk_byte_ix, ..) = src.chunk_at_byte(byte_ix);
-        &s[byte_ix - chunk_byte_ix..]
-      },
-      None,
-    )
-    .ok_or(Error::Other)
-    .report()
-    .attach_printable("failed to parse source")
-}
+/// Returns the source files given by `opts`, walking directories with the
+/// languages set by `configs`, or [`None`] if the source is read from the
+/// standard input.
+fn srcs(
+  opts: &Opts,
+  configs: &mut Configs,
+) -> Result<Option<Vec<PathBuf>>, Error> {
+  if opts.srcs.is_empty() && opts.files_from.is_none() {
+    return Ok(None);
+  }
 
-#[inline]
-fn query_src_from_opts(opts: &Opts) -> Result<String, Error> {
-  fs::read_to_string(&opts.query).report().change_context(Error::QueryFile)
+  let filter = Filter::new(&opts.include, &opts.exclude)?;
+  let mut srcs = match &opts.files_from {
+    None => Vec::new(),
+    Some(path) => files_from(path)?,
+  };
+  srcs.retain(|path| filter.keeps(path));
+  for path in &opts.srcs {
+    match path.is_dir() {
+      false if filter.keeps(path) => srcs.push(path.clone()),
+      false => log::trace!("skipping {}", path.display()),
+      true => srcs.extend(walk(
+        path,
+        opts.lang,
+        opts.query.is_some(),
+        &filter,
+        configs,
+      )?),
+    }
+  }
+  Ok(Some(srcs))
 }
 
-struct ChunksBytes<'a>(Chunks<'a>);
-
-impl<'a> Iterator for ChunksBytes<'a> {
-  type Item = &'a [u8];
-
-  #[inline]
-  fn next(&mut self) -> Option<Self::Item> { self.0.next().map(str::as_bytes) }
+/// Prints the builtin predicates and settings.
+fn list() -> Result<(), Error> {
+  let predicates =
+    Predicates::builtin().report().change_context(Error::Other)?;
+  let parsers = Parsers::builtin().report().change_context(Error::Other)?;
+  println!("predicates:");
+  predicates.names().for_each(|name| println!("  {name}"));
+  println!("settings:");
+  parsers.keys().for_each(|key| println!("  {key}"));
+  Ok(())
 }
 
-#[derive(Clone)]
-struct RopeProvider<'a>(RopeSlice<'a>);
-
-impl<'a> TextProvider<'a> for RopeProvider<'a> {
-  type I = ChunksBytes<'a>;
+/// Key of a formatter: its language, query file and configuration directory.
+type FormatterKey = (Lang, PathBuf, Option<PathBuf>);
 
-  #[inline]
-  fn text(&mut self, node: Node<'_>) -> Self::I {
-    ChunksBytes(self.0.byte_slice(node.byte_range()).chunks())
-  }
+/// Cooker of sources, creating a formatter per language, query file and
+/// configuration.
+struct Cook<'o> {
+  opts: &'o Opts,
+  configs: Configs,
+  formatters: FnvHashMap<FormatterKey, Formatter>,
 }
 
-fn cook(
-  parser: &mut Parser,
-  src: RopeSlice<'_>,
-  lang: Language,
-  query_src: &str,
-  query_cursor: &mut QueryCursor,
-  setting_parsers: &SettingParsers<'_>,
-  predicates: &Predicates<'_>,
-) -> Result<Rope, Error> {
-  parser.set_language(lang).map_err(Error::Lang)?;
-  let tree = parse_rope_slice(src, parser)?;
-  let query = Query::new(lang, query_src).map_err(Error::Query)?;
-  let mut editor = Editor::from(Rope::from(src));
-  let mut settings = Settings::default();
-
-  let matches = Matches::from(query_cursor.matches(
-    &query,
-    tree.root_node(),
-    RopeProvider(src),
-  ));
-
-  for (pat_ix, cap_ix_to_nodes_slice) in matches.iter() {
-    log::trace!("applying pattern #{pat_ix}");
-
-    let is_pat_rooted = query.is_pattern_rooted(pat_ix);
-    let scope = match is_pat_rooted {
-      false => Scope::Global,
-      true => Scope::Local,
-    };
+impl<'o> Cook<'o> {
+  fn new(opts: &'o Opts) -> Self {
+    Self {
+      opts,
+      configs: Configs::default(),
+      formatters: FnvHashMap::default(),
+    }
+  }
 
-    for nodes_provider in cap_ix_to_nodes_slice.iter().map(|cap_ix_to_nodes| {
-      Provider::new(cap_ix_to_nodes, matches.id_to_node())
-    }) {
-      for query_prop in query.property_settings(pat_ix).iter() {
-        setting_parsers
-          .parse(query_prop, scope, &nodes_provider, &mut settings)
-          .change_context_lazy(|| {
-            Error::setting(pat_ix, query_prop.key.as_ref())
-          })?;
+  /// Returns the formatter of `lang` with the query file at `query` and the
+  /// defaults of `config`, creating it if needed.
+  fn formatter(
+    &mut self,
+    lang: Lang,
+    query: PathBuf,
+    config: &Config,
+  ) -> Result<&Formatter, Error> {
+    Ok(match self.formatters.entry((lang, query, config.dir.clone())) {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => {
+        let opts = self.opts;
+        let query = &entry.key().1;
+        log::info!("reading query from {}", query.display());
+        let query_src = fs::read_to_string(query)
+          .report()
+          .attach_printable_lazy(|| query.display().to_string())
+          .change_context(Error::QueryFile)?;
+        let mut formatter = Formatter::new(lang.into(), &query_src)
+          .map_err(Error::Format)?
+          .with_options(Options {
+            policy: opts.conflicts.into(),
+            max_passes: opts.fixpoint.then_some(opts.max_passes.get()),
+            verify: !opts.no_verify,
+            check_idempotent: opts.check_idempotent,
+          });
+        for (key, value) in &config.settings {
+          formatter.set_default(key, value).map_err(Error::Format)?;
+        }
+        for (name, _) in config.predicates.iter().filter(|(_, on)| !**on) {
+          if !formatter.disable_predicate(name) {
+            log::warn!("cannot disable unknown predicate \"{name}\"");
+          }
+        }
+        entry.insert(formatter)
       }
+    })
+  }
 
-      for query_predicate in query.general_predicates(pat_ix) {
-        let op = query_predicate.operator.as_ref();
-        predicates
-          .parse(
-            &query,
-            query_predicate,
-            scope,
-            &nodes_provider,
-            &mut settings,
-            &mut editor,
-          )
-          .change_context_lazy(|| Error::predicate(op, pat_ix))?;
-      }
+  /// Cooks the source at `path`, or from the standard input, returning it
+  /// along with the output.
+  fn cook(&mut self, path: Option<&Path>) -> Result<(Rope, Rope), Error> {
+    let path = match path {
+      None => None,
+      Some(path) => Some(
+        fs::canonicalize(path)
+          .report()
+          .attach_printable_lazy(|| path.display().to_string())
+          .change_context(Error::SrcFile)?,
+      ),
+    };
+    let path = path.as_deref();
+    let dir = match path.and_then(Path::parent) {
+      None => env::current_dir().report().change_context(Error::Other)?,
+      Some(dir) => dir.to_path_buf(),
+    };
+    let config = self.configs.get(&dir)?;
+    let lang = lang(self.opts, &config, path)?;
+    let query = match &self.opts.query {
+      None => match config.query(path, lang) {
+        None => bail!(Error::QueryMissing),
+        Some(query) => query.to_path_buf(),
+      },
+      Some(query) => query.clone(),
+    };
+    let src = src_rope(path)?;
+    let out = self
+      .formatter(lang, query, &config)?
+      .format_rope(&src)
+      .map_err(Error::Format)
+      .report()?;
+    Ok((src, out))
+  }
 
-      settings.reset();
+  /// Cooks the source at `path` in place.
+  fn write(&mut self, path: &Path) -> Result<Outcome, Error> {
+    let (src, out) = self.cook(Some(path))?;
+    cli::write(path, &src, &out).report().change_context(Error::Write)
+  }
 
-      if !is_pat_rooted {
-        log::trace!("skipping redundant non-rooted matches");
-        break;
-      }
+  /// Checks whether cooking the source at `path`, or from the standard input,
+  /// would change it, printing the diff if `opts.diff`.
+  fn check(&mut self, path: Option<&Path>) -> Result<Outcome, Error> {
+    let (src, out) = self.cook(path)?;
+    if self.opts.diff {
+      let name = path.map_or("<stdin>".into(), Path::to_string_lossy);
+      let color = atty::is(atty::Stream::Stdout);
+      print!("{}", cli::diff(&name, &src, &out, color));
     }
+    Ok(Outcome::of(&src, &out))
   }
+}
 
-  Ok(editor.into())
+/// Cooks the sources in place, or checks them if `opts.check` or `opts.diff`,
+/// reporting the failures as they happen and returning a summary.
+///
+/// The sources which would change are printed when checking without diffs.
+fn cook_all(opts: &Opts) -> Result<Summary, Error> {
+  let mut cook = Cook::new(opts);
+  let dry = opts.check || opts.diff;
+  let mut summary = Summary::new(dry);
+  let srcs = srcs(opts, &mut cook.configs)?;
+  let paths = match &srcs {
+    None => vec![None],
+    Some(srcs) => srcs.iter().map(|path| Some(path.as_path())).collect(),
+  };
+  for path in paths {
+    let name = path.map_or("<stdin>".into(), Path::to_string_lossy);
+    let outcome = match (dry, path) {
+      (true, _) => cook.check(path),
+      (false, Some(path)) => cook.write(path),
+      (false, None) => unreachable!(),
+    }
+    .map_err(|err| {
+      eprintln!("{name}: {err}");
+      log::error!("{err:?}");
+    })
+    .ok();
+    if opts.check && !opts.diff && outcome == Some(Outcome::Changed) {
+      println!("{name}");
+    }
+    summary.count(outcome);
+  }
+  Ok(summary)
 }
 
 #[inline]
-fn cook_from_cli() -> Result<Rope, Error> {
-  let opts = <Opts as clap::Parser>::parse();
-  let lang = ts_lang_from_opts(&opts)?;
-  let mut parser = Parser::new();
-  let src = src_rope_from_opts(&opts)?;
-  let query_src = query_src_from_opts(&opts)?;
-  let mut query_cursor = QueryCursor::new();
-  let setting_parsers = SettingParsers::default();
-  let predicates = Predicates::default();
-
-  cook(
-    &mut parser,
-    src.slice(..),
-    lang,
-    &query_src,
-    &mut query_cursor,
-    &setting_parsers,
-    &predicates,
-  )
+fn cook_from_cli(opts: &Opts) -> Result<Rope, Error> {
+  let mut cook = Cook::new(opts);
+  let srcs = srcs(opts, &mut cook.configs)?;
+  let path = match srcs.as_deref() {
+    None => None,
+    Some([path]) => Some(path.as_path()),
+    Some(_) => bail!(Error::Srcs),
+  };
+  let (_, out) = cook.cook(path)?;
+  Ok(out)
 }
 
 fn main() -> ExitCode {
   env_logger::init();
-  match cook_from_cli() {
-    Err(err) => {
-      match err.current_context() {
-        predicate_err @ Error::Predicate { .. } => eprintln!(
-          "{predicate_err}: {}",
-          err.downcast_ref::<PredicateErr>().unwrap(),
-        ),
-        setting_err @ Error::Setting { .. } => {
-          eprintln!(
-            "{setting_err}: {}",
-            err.downcast_ref::<ParseSettingErr>().unwrap(),
-          )
+  let opts = <Opts as clap::Parser>::parse();
+  if opts.list {
+    return match list() {
+      Err(err) => {
+        eprintln!("{err}");
+        ExitCode::FAILURE
+      }
+      Ok(()) => ExitCode::SUCCESS,
+    };
+  }
+
+  if opts.check || opts.diff || opts.write {
+    return match cook_all(&opts) {
+      Err(err) => {
+        eprintln!("{err}");
+        log::error!("{err:?}");
+        ExitCode::from(EXIT_ERROR)
+      }
+      Ok(summary) => {
+        eprintln!("{summary}");
+        match (summary.failed, summary.changed) {
+          (0, 0) => ExitCode::SUCCESS,
+          (0, _) if opts.check => ExitCode::from(EXIT_CHANGED),
+          (0, _) => ExitCode::SUCCESS,
+          _ => ExitCode::from(EXIT_ERROR),
         }
-        _ => eprintln!("{err}"),
       }
+    };
+  }
+
+  match cook_from_cli(&opts) {
+    Err(err) => {
+      eprintln!("{err}");
       log::error!("{err:?}");
       ExitCode::FAILURE
     }
@@ -1,16 +1,13 @@
-mod jumper;
 mod walker;
 
+pub use walker::Walker;
 use {
   fnv::FnvHashMap,
   std::{borrow::Cow, fmt},
   tree_sitter::{Node, QueryMatches, TextProvider},
 };
-pub use {
-  jumper::{Item as JumperItem, Jumper},
-  walker::{Item as WalkerItem, Walker},
-};
 
+/// Displays a node by its kind and range.
 pub struct Displayer<'a, 'tree>(pub &'a Node<'tree>);
 
 impl<'a, 'tree> fmt::Display for Displayer<'a, 'tree> {
@@ -32,9 +29,10 @@ impl<'a, 'tree> fmt::Display for Displayer<'a, 'tree> {
   }
 }
 
-pub type CapIxToNodes<'tree> = FnvHashMap<u32, Vec<Node<'tree>>>;
-pub type IdToNode<'tree> = FnvHashMap<usize, Node<'tree>>;
+pub(crate) type CapIxToNodes<'tree> = FnvHashMap<u32, Vec<Node<'tree>>>;
+pub(crate) type IdToNode<'tree> = FnvHashMap<usize, Node<'tree>>;
 
+/// Provider of the nodes of a match.
 pub struct Provider<'a, 'tree> {
   cap_ix_to_nodes: &'a CapIxToNodes<'tree>,
   id_to_node: &'a IdToNode<'tree>,
@@ -42,34 +40,44 @@ pub struct Provider<'a, 'tree> {
 
 impl<'a, 'tree> Provider<'a, 'tree> {
   #[inline]
-  pub fn new(
+  pub(crate) fn new(
     cap_ix_to_nodes: &'a CapIxToNodes<'tree>,
     ix_to_node: &'a IdToNode<'tree>,
   ) -> Self {
     Self { cap_ix_to_nodes, id_to_node: ix_to_node }
   }
 
+  /// Returns the nodes captured by the capture of index `ix`.
   pub fn nodes_for_cap_ix(
     &self,
     ix: u32,
   ) -> impl Iterator<Item = &Node<'tree>> + '_ {
-    self.cap_ix_to_nodes.get(&ix).map(Vec::as_slice).unwrap_or(&[]).iter()
+    self.nodes_slice_for_cap_ix(ix).iter()
+  }
+
+  /// Returns the nodes captured by the capture of index `ix`, in the order
+  /// they were captured.
+  #[inline]
+  pub fn nodes_slice_for_cap_ix(&self, ix: u32) -> &[Node<'tree>] {
+    self.cap_ix_to_nodes.get(&ix).map(Vec::as_slice).unwrap_or(&[])
   }
 
+  /// Returns the node of `id` captured by any match.
   #[inline]
   pub fn node_for_id(&self, id: usize) -> Option<&Node<'tree>> {
     self.id_to_node.get(&id)
   }
 
+  /// Returns the nodes captured by the match.
   pub fn nodes_for_match(&self) -> impl Iterator<Item = &Node<'tree>> + '_ {
     self.cap_ix_to_nodes.values().flatten()
   }
 }
 
-pub type PatIxToMatchNodes<'tree> =
+pub(crate) type PatIxToMatchNodes<'tree> =
   FnvHashMap<usize, Vec<CapIxToNodes<'tree>>>;
 
-pub struct Matches<'tree> {
+pub(crate) struct Matches<'tree> {
   pat_ix_to_match_nodes: PatIxToMatchNodes<'tree>,
   id_to_node: IdToNode<'tree>,
 }
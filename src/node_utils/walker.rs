@@ -38,13 +38,27 @@ impl<'a, 'tree> Into<(&'a Node<'tree>, usize, Option<&'static str>)>
   }
 }
 
+/// Predicate of the nodes whose descendants are skipped.
+type Opaque<'cursor, 'tree> = Box<dyn Fn(&Node<'tree>) -> bool + 'cursor>;
+
+/// Iterator over the nodes of a tree cursor, in pre-order.
 pub struct Walker<'cursor, 'tree> {
   cursor: &'cursor mut TreeCursor<'tree>,
   exhausted: bool,
   depth: usize,
+  opaque: Option<Opaque<'cursor, 'tree>>,
 }
 
 impl<'cursor, 'tree> Walker<'cursor, 'tree> {
+  /// Makes the walker skip the descendants of the nodes `opaque` holds for.
+  pub fn with_opaque(
+    mut self,
+    opaque: impl Fn(&Node<'tree>) -> bool + 'cursor,
+  ) -> Self {
+    self.opaque = Some(Box::new(opaque));
+    self
+  }
+
   #[inline]
   fn up_next(&mut self) {
     let index = self.cursor.node().end_byte() + 1;
@@ -66,7 +80,9 @@ impl<'cursor, 'tree> Walker<'cursor, 'tree> {
 
   #[inline]
   fn walk(&mut self) {
-    if self.cursor.goto_first_child() {
+    let opaque =
+      self.opaque.as_ref().is_some_and(|opaque| opaque(&self.cursor.node()));
+    if !opaque && self.cursor.goto_first_child() {
       self.depth += 1;
     } else if self.depth == 0 {
       self.exhausted = true;
@@ -82,7 +98,7 @@ impl<'cursor, 'tree> From<&'cursor mut TreeCursor<'tree>>
 {
   #[inline]
   fn from(cursor: &'cursor mut TreeCursor<'tree>) -> Self {
-    Walker { cursor, exhausted: false, depth: 0 }
+    Walker { cursor, exhausted: false, depth: 0, opaque: None }
   }
 }
 
@@ -145,4 +161,27 @@ mod tests {
 
     assert!(walker.next().is_none());
   }
+
+  #[test]
+  fn walker_opaque() {
+    let src = "fn foo() { bar(); }";
+
+    let mut parser = Parser::new();
+    parser.set_language(tree_sitter_rust::language()).unwrap();
+    let tree = parser.parse(src, None).unwrap();
+    let mut cursor = tree.walk();
+    let kinds = Walker::from(&mut cursor)
+      .with_opaque(|node| node.kind() == "parameters")
+      .map(|item| item.node().kind())
+      .take(6)
+      .collect::<Vec<_>>();
+    assert_eq!(kinds, [
+      "source_file",
+      "function_item",
+      "fn",
+      "identifier",
+      "parameters",
+      "block",
+    ]);
+  }
 }
@@ -0,0 +1,172 @@
+use {
+  crate::{predicates::prelude::*, settings::AlignStyle},
+  std::iter,
+};
+
+/// Returns the number of columns `prefix` spans, expanding its tabs.
+pub(crate) fn width(prefix: &str) -> usize {
+  prefix.chars().fold(0, |col, ch| match ch {
+    '\t' => (col / AlignStyle::TAB_WIDTH + 1) * AlignStyle::TAB_WIDTH,
+    _ => col + 1,
+  })
+}
+
+/// Returns the padding taking each of `cols` to the same column with `style`.
+fn paddings(cols: &[usize], style: AlignStyle) -> Vec<String> {
+  let max = cols.iter().copied().max().unwrap_or_default();
+  match style {
+    AlignStyle::Spaces => {
+      cols.iter().map(|col| " ".repeat(max - col)).collect()
+    }
+    AlignStyle::Tabs => {
+      let stop = max.div_ceil(AlignStyle::TAB_WIDTH);
+      cols
+        .iter()
+        .map(|col| "\t".repeat(stop - col / AlignStyle::TAB_WIDTH))
+        .collect()
+    }
+  }
+}
+
+/// Returns the end of the token preceding `node`, if any.
+fn prev_end(node: &Node<'_>) -> Option<usize> {
+  iter::successors(Some(*node), Node::parent)
+    .find_map(|node| node.prev_sibling())
+    .map(|prev| prev.end_byte())
+}
+
+pub struct Align;
+
+impl Predicate for Align {
+  fn name(&self) -> &'static str { "align!" }
+
+  fn parse<'a, 'tree>(
+    &self,
+    _query: &Query,
+    args: &'a [QueryPredicateArg],
+    _scope: Scope,
+    nodes_provider: &Provider<'_, 'tree>,
+    settings: &mut Settings<'a, 'tree>,
+    editor: &mut Editor,
+  ) -> Result<(), Error> {
+    ensure!(args.len() == 1, Error::nargs(1, args.len()));
+    let cap_ix = match &args[0] {
+      QueryPredicateArg::Capture(ix) => *ix,
+      QueryPredicateArg::String(s) => {
+        bail!(Error::arg(0, "capture", format!("\"{s}\"")))
+      }
+    };
+    let style = settings.align_style().unwrap_or_default();
+
+    let src = editor.src();
+    let mut groups: Vec<Vec<&Node<'tree>>> = Vec::new();
+    let mut last_row = None;
+    for node in nodes_provider.nodes_for_cap_ix(cap_ix) {
+      let row = node.start_position().row;
+      match last_row {
+        Some(last_row) if row == last_row => continue,
+        Some(last_row)
+          if (last_row + 1..row).all(|row| {
+            !src.line(row).chars().all(|ch| ch.is_whitespace())
+          }) =>
+        {
+          groups.last_mut().unwrap().push(node)
+        }
+        _ => groups.push(vec![node]),
+      }
+      last_row = Some(row);
+    }
+
+    for group in groups.into_iter().filter(|group| group.len() > 1) {
+      let cols = group
+        .iter()
+        .map(|node| width(&editor.line_prefix(node.start_byte(), Bias::Right)))
+        .collect::<Vec<_>>();
+      for (node, pad) in group.into_iter().zip(paddings(&cols, style)) {
+        if pad.is_empty() {
+          continue;
+        }
+        let start = prev_end(node).unwrap_or_else(|| node.start_byte());
+        let range = start..node.start_byte();
+        let gap = editor.text(range.clone());
+        editor.replace(range, &format!("{gap}{pad}")).map_err(Error::Edit)?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{predicates::spacer::Spacer, query_testing::prelude::*},
+  };
+
+  #[test]
+  fn paddings_() {
+    assert_eq!(paddings(&[3, 5, 4], AlignStyle::Spaces), ["  ", "", " "]);
+    assert_eq!(paddings(&[3, 9, 8], AlignStyle::Tabs), ["\t\t", "\t", "\t"]);
+    assert_eq!(paddings(&[8, 16], AlignStyle::Tabs), ["\t", ""]);
+    assert_eq!(width("\tab\tc"), 17);
+  }
+
+  #[test]
+  fn align() {
+    let cook = |style| {
+      cook_debugging(
+        lines!(
+          "fn f() {",
+          "  let a = 1 ;",
+          "  let bcd  =  2;",
+          "  let",
+          "    e = 3 ;",
+          "",
+          "  let fg=4; let h = 5 ;",
+          "  let i = 6 ;",
+          "}",
+        ),
+        &[
+          &format!(r#"(#set! align-style "{style}")"#),
+          r#"((let_declaration) @let (#spacer! @let))"#,
+          r#"((block (let_declaration "=" @eq)+) (#align! @eq))"#,
+        ]
+        .join("\n"),
+        |_, _, _, _, _, _| {},
+        |setting_parsers| *setting_parsers = Parsers::builtin().unwrap(),
+        |predicates| {
+          predicates.push(Align).unwrap();
+          predicates.push(Spacer).unwrap();
+        },
+      )
+      .unwrap()
+    };
+    assert_eq!(
+      cook("spaces"),
+      lines!(
+        "fn f() {",
+        "  let a   = 1 ;",
+        "  let bcd = 2 ;",
+        "  let e   = 3 ;",
+        "",
+        "  let fg = 4 ; let h = 5 ;",
+        "  let i  = 6 ;",
+        "}",
+      ),
+    );
+    assert_eq!(
+      cook("tabs"),
+      lines!(
+        "fn f() {",
+        "  let a \t= 1 ;",
+        "  let bcd \t= 2 ;",
+        "  let e \t= 3 ;",
+        "",
+        "  let fg \t= 4 ; let h = 5 ;",
+        "  let i \t= 6 ;",
+        "}",
+      ),
+    );
+  }
+}
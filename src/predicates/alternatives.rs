@@ -0,0 +1,175 @@
+use crate::{predicates::prelude::*, settings::Cpl};
+
+/// Candidate layout of [`Alternatives`], costing `penalty` more than others.
+#[derive(Clone)]
+pub(crate) struct Candidate {
+  pub name: String,
+  pub penalty: usize,
+}
+
+/// Candidate layouts of a node, the cheapest of which is chosen.
+pub(crate) struct Alternatives<'tree> {
+  pub node: Node<'tree>,
+  pub candidates: Vec<Candidate>,
+  pub cpl: Option<Cpl>,
+}
+
+pub struct AlternativesPredicate;
+
+impl Predicate for AlternativesPredicate {
+  fn name(&self) -> &'static str { "alternatives!" }
+
+  fn parse<'a, 'tree>(
+    &self,
+    _query: &Query,
+    args: &'a [QueryPredicateArg],
+    _scope: Scope,
+    nodes_provider: &Provider<'_, 'tree>,
+    settings: &mut Settings<'a, 'tree>,
+    _editor: &mut Editor,
+  ) -> Result<(), Error> {
+    let cap_ix = match args.first() {
+      None => bail!(Error::nargs("at least 2", 0)),
+      Some(QueryPredicateArg::Capture(ix)) => *ix,
+      Some(QueryPredicateArg::String(s)) => {
+        bail!(Error::arg(0, "capture", format!("\"{s}\"")))
+      }
+    };
+    ensure!(args.len() > 1, Error::nargs("at least 2", args.len()));
+
+    let candidates = args[1..]
+      .iter()
+      .enumerate()
+      .map(|(ix, arg)| {
+        let s = match arg {
+          QueryPredicateArg::String(s) => s,
+          QueryPredicateArg::Capture(_) => {
+            bail!(Error::arg(ix + 1, "candidate", "capture"))
+          }
+        };
+        let (name, penalty) = match s.split_once('=') {
+          None => (s.as_ref(), 0),
+          Some((name, penalty)) => (
+            name,
+            penalty.parse().map_err(|_| {
+              Error::arg(ix + 1, "usize penalty", format!("\"{penalty}\""))
+            })?,
+          ),
+        };
+        Ok(Candidate { name: name.to_string(), penalty })
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let cpl = settings.cpl();
+    for node in nodes_provider.nodes_for_cap_ix(cap_ix) {
+      settings.alternatives_mut().push(Alternatives {
+        node: *node,
+        candidates: candidates.clone(),
+        cpl,
+      });
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::query_testing::prelude::*;
+
+  fn cook(src: &str, candidates: &str) -> String {
+    let query = [
+      "(#set! cpl 32)",
+      &format!("((arguments) @args (#alternatives! @args {candidates}))"),
+      r#"((arguments "," @comma . (_) @next) @args"#,
+      r#" (#set! @args alternative "flat")"#,
+      r#" (#space! @comma @next))"#,
+      r#"((arguments "," @comma . (_) @next) @args"#,
+      r#" (#set! @args alternative "wide")"#,
+      r#" (#space! "  " @comma @next))"#,
+      r#"((arguments "," @comma . (_) @next) @args"#,
+      r#" (#set! @args alternative "tall")"#,
+      r#" (#space! "\n    " @comma @next))"#,
+    ]
+    .join("\n");
+    cook_with(src, &query, |_| {}, Options::default()).unwrap().to_string()
+  }
+
+  #[test]
+  fn alternatives() {
+    let src = lines!(
+      "fn f() {",
+      "  foo(alpha,beta);",
+      "  foo(alpha,beta,gamma,delta,epsilon);",
+      "}",
+    );
+    assert_eq!(
+      cook(src, r#""flat" "tall=1""#),
+      lines!(
+        "fn f() {",
+        "  foo(alpha, beta);",
+        "  foo(alpha,",
+        "    beta,",
+        "    gamma,",
+        "    delta,",
+        "    epsilon);",
+        "}",
+      ),
+    );
+    assert_eq!(
+      cook(src, r#""flat=4" "tall""#),
+      lines!(
+        "fn f() {",
+        "  foo(alpha,",
+        "    beta);",
+        "  foo(alpha,",
+        "    beta,",
+        "    gamma,",
+        "    delta,",
+        "    epsilon);",
+        "}",
+      ),
+    );
+  }
+
+  #[test]
+  fn alternatives_settings() {
+    let query = [
+      "(#set! cpl 32)",
+      r#"((arguments) @args (#alternatives! @args "keep" "tall"))"#,
+      r#"((arguments) @args"#,
+      r#" (#set! @args alternative "keep")"#,
+      r#" (#set! @args verbatim))"#,
+      r#"((arguments "," @comma . (_) @next) @args"#,
+      r#" (#set! @args alternative "tall")"#,
+      r#" (#space! "\n    " @comma @next))"#,
+    ]
+    .join("\n");
+    let src = lines!(
+      "fn f() {",
+      "  foo(alpha, beta, gamma, delta, epsilon, zeta, eta);",
+      "}",
+    );
+    assert_eq!(
+      cook_with(src, &query, |_| {}, Options::default()).unwrap().to_string(),
+      lines!(
+        "fn f() {",
+        "  foo(alpha,",
+        "    beta,",
+        "    gamma,",
+        "    delta,",
+        "    epsilon,",
+        "    zeta,",
+        "    eta);",
+        "}",
+      ),
+    );
+  }
+
+  #[test]
+  fn alternatives_tie() {
+    let src = "fn f() { foo(a,b); }";
+    assert_eq!(cook(src, r#""flat" "wide""#), "fn f() { foo(a, b); }");
+    assert_eq!(cook(src, r#""wide" "flat""#), "fn f() { foo(a,  b); }");
+  }
+}
@@ -32,13 +32,14 @@ where
 impl<F> Predicate for Debugger<F>
 where
   F: Fn(
-    &Query,
-    &[QueryPredicateArg],
-    Scope,
-    &Provider<'_, '_>,
-    &Settings<'_, '_>,
-    &Editor,
-  ),
+      &Query,
+      &[QueryPredicateArg],
+      Scope,
+      &Provider<'_, '_>,
+      &Settings<'_, '_>,
+      &Editor,
+    ) + Send
+    + Sync,
 {
   fn name(&self) -> &'static str { self.name }
 
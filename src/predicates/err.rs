@@ -1,38 +1,62 @@
-use {std::string::ToString, tree_sitter::Query};
-
-#[derive(Debug, thiserror::Error)]
+use {
+  crate::editor::Error as EditErr,
+  std::string::ToString,
+  tree_sitter::Query,
+};
+
+/// Error of a [`Predicate`].
+///
+/// [`Predicate`]: crate::predicates::Predicate
+#[derive(Clone, Debug, thiserror::Error)]
 pub enum Error {
+  /// The argument `ix` is not the `expected` one.
+  #[allow(missing_docs)]
   #[error("invalid argument #{ix}; expected {expected}, got {got}")]
   Arg { ix: usize, expected: String, got: String },
 
+  /// The capture `name` is invalid.
+  #[allow(missing_docs)]
   #[error("error with capture \"{name}\": {msg}")]
   Cap { name: String, msg: String },
 
+  /// The source could not be edited.
+  #[error("failed to edit source: {0}")]
+  Edit(EditErr),
+
+  /// The number of arguments is not the `expected` one.
+  #[allow(missing_docs)]
   #[error("invalid number of arguments; expected {expected}, got {got}")]
   Nargs { expected: String, got: String },
 
+  /// No predicate is named after the operator.
   #[error("invalid predicate operator \"{0}\"")]
   Op(String),
 
+  /// Any other error.
   #[error("{0}")]
   Other(String),
 }
 
 impl Error {
+  /// Creates an [`Error::Arg`].
   pub fn arg(ix: usize, expected: impl ToString, got: impl ToString) -> Self {
     Self::Arg { ix, expected: expected.to_string(), got: got.to_string() }
   }
 
+  /// Creates an [`Error::Cap`] for the capture of index `ix` in `query`.
   pub fn cap(query: &Query, ix: u32, msg: impl ToString) -> Self {
     let name = query.capture_names()[ix as usize].clone();
     Self::Cap { name, msg: msg.to_string() }
   }
 
+  /// Creates an [`Error::Nargs`].
   pub fn nargs(expected: impl ToString, got: impl ToString) -> Self {
     Self::Nargs { expected: expected.to_string(), got: got.to_string() }
   }
 
+  /// Creates an [`Error::Op`].
   pub fn op(s: impl ToString) -> Self { Self::Op(s.to_string()) }
 
+  /// Creates an [`Error::Other`].
   pub fn other(s: impl ToString) -> Self { Self::Other(s.to_string()) }
 }
@@ -0,0 +1,160 @@
+use {
+  crate::predicates::prelude::*,
+  std::{iter, ops::Range},
+};
+
+/// Returns the root of the tree of `node`.
+pub(super) fn root<'tree>(node: &Node<'tree>) -> Node<'tree> {
+  iter::successors(Some(*node), Node::parent).last().unwrap()
+}
+
+/// Returns the ranges of the whitespace runs of the original `gap`, split by
+/// the tokens of `root` located in it, such as comments, along with these
+/// tokens.
+pub(super) fn whitespace_runs<'tree>(
+  gap: Range<usize>,
+  root: &Node<'tree>,
+  editor: &Editor,
+) -> (Vec<Range<usize>>, Vec<Node<'tree>>) {
+  let src = editor.src();
+  let mut runs = Vec::new();
+  let mut tokens = Vec::new();
+  let mut byte = gap.start;
+
+  loop {
+    let token_start = byte
+      + src
+        .byte_slice(byte..gap.end)
+        .chars()
+        .take_while(char::is_ascii_whitespace)
+        .map(char::len_utf8)
+        .sum::<usize>();
+    runs.push(byte..token_start);
+    if token_start == gap.end {
+      break (runs, tokens);
+    }
+
+    let token = root
+      .descendant_for_byte_range(token_start, token_start)
+      .filter(|token| token.start_byte() == token_start);
+    tokens.extend(token);
+    byte = token
+      .map(|token| token.end_byte())
+      .filter(|end| *end > token_start)
+      .unwrap_or_else(|| {
+        token_start
+          + src
+            .byte_slice(token_start..gap.end)
+            .chars()
+            .take_while(|ch| !ch.is_ascii_whitespace())
+            .map(char::len_utf8)
+            .sum::<usize>()
+      })
+      .min(gap.end);
+  }
+}
+
+/// Returns whether the gap between `a` and `b` is located within a verbatim
+/// node, according to `settings`.
+pub(super) fn is_verbatim<'tree>(
+  a: &Node<'tree>,
+  b: &Node<'tree>,
+  settings: &Settings<'_, 'tree>,
+) -> bool {
+  let verbatim =
+    settings.verbatim_ancestor(a).or_else(|| settings.verbatim_ancestor(b));
+  if let Some(node) = verbatim {
+    log::trace!("not spacing within verbatim {}", Displayer(&node));
+  }
+  verbatim.is_some()
+}
+
+/// Replaces the whitespaces between `a` and `b` with `sep`, keeping anything
+/// else located between them.
+///
+/// Whitespace runs next to a comment keep their line breaks, and get spaced
+/// with a space at least, so that comments stay in their place. Gaps within
+/// verbatim nodes are left alone.
+pub(super) fn space<'tree>(
+  a: &Node<'tree>,
+  b: &Node<'tree>,
+  sep: &str,
+  settings: &Settings<'_, 'tree>,
+  editor: &mut Editor,
+) -> Result<(), Error> {
+  if is_verbatim(a, b, settings) {
+    return Ok(());
+  }
+
+  let (runs, tokens) =
+    whitespace_runs(a.end_byte()..b.start_byte(), &root(a), editor);
+  for token in tokens.iter().filter(|token| !token.is_extra()) {
+    log::warn!(
+      "keeping non-whitespace {} between {} and {}",
+      Displayer(token),
+      Displayer(a),
+      Displayer(b),
+    );
+  }
+
+  let last = runs.len() - 1;
+  for (ix, run) in runs.into_iter().enumerate() {
+    let follows_token = ix > 0 || a.is_extra();
+    let precedes_token = ix < last || b.is_extra();
+    let sep = match follows_token || precedes_token {
+      false => sep,
+      true if editor.text(run.clone()).contains('\n') => continue,
+      true if sep.is_empty() => " ",
+      true => sep,
+    };
+    editor.replace(run, sep).map_err(Error::Edit)?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    predicates::{space::Space, spacer::Spacer},
+    query_testing::prelude::*,
+  };
+
+  fn cook(src: &str, query: &str) -> String {
+    cook_debugging(
+      src,
+      query,
+      |_, _, _, _, _, _| {},
+      |_| {},
+      |predicates| {
+        predicates.push(Space).unwrap();
+        predicates.push(Spacer).unwrap();
+      },
+    )
+    .unwrap()
+    .to_string()
+  }
+
+  #[test]
+  fn space_keeps_comments() {
+    let query = r#"((block "{" @open (expression_statement) @stmt) (#space! "" @open @stmt))"#;
+    assert_eq!(
+      cook("fn f() {  /* a */g();}", query),
+      "fn f() { /* a */ g();}"
+    );
+    assert_eq!(
+      cook("fn f() {  // a\n  g();}", query),
+      "fn f() { // a\n  g();}",
+    );
+    assert_eq!(cook("fn f() {  g();}", query), "fn f() {g();}");
+  }
+
+  #[test]
+  fn spacer_keeps_comments() {
+    let query = "((function_item) @fn (#spacer! @fn))";
+    assert_eq!(
+      cook("fn f(){// a\ng();/*b*/}", query),
+      "fn f ( ) { // a\ng ( ) ; /*b*/ }",
+    );
+  }
+}
@@ -0,0 +1,36 @@
+use crate::predicates::{layout, prelude::*};
+
+pub struct Group;
+
+impl Predicate for Group {
+  fn name(&self) -> &'static str { "group!" }
+
+  fn parse<'a, 'tree>(
+    &self,
+    _query: &Query,
+    args: &'a [QueryPredicateArg],
+    _scope: Scope,
+    nodes_provider: &Provider<'_, 'tree>,
+    settings: &mut Settings<'a, 'tree>,
+    _editor: &mut Editor,
+  ) -> Result<(), Error> {
+    ensure!(!args.is_empty(), Error::nargs("at least 1", 0));
+
+    for (ix, arg) in args.iter().enumerate() {
+      let cap_ix = match arg {
+        QueryPredicateArg::Capture(ix) => *ix,
+        QueryPredicateArg::String(s) => {
+          bail!(Error::arg(ix, "capture", format!("\"{s}\"")))
+        }
+      };
+
+      let cpl = settings.cpl();
+      for node in nodes_provider.nodes_for_cap_ix(cap_ix) {
+        log::trace!("grouping {}", Displayer(node));
+        settings.layout_mut().push_group(layout::Group { node: *node, cpl });
+      }
+    }
+
+    Ok(())
+  }
+}
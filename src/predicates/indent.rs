@@ -1,4 +1,8 @@
-use {crate::predicates::prelude::*, ropey::RopeSlice, tree_sitter::Point};
+use {
+  crate::{predicates::prelude::*, settings::IndentRule},
+  ropey::RopeSlice,
+  std::iter,
+};
 
 /// Returns the number of leading [`ascii_whitespaces`] at `row` from `slice`.
 ///
@@ -11,6 +15,91 @@ fn ascii_whitespaces(row: usize, slice: RopeSlice<'_>) -> usize {
     .count()
 }
 
+/// Returns the leading [`ascii_whitespaces`] of `s`.
+///
+/// [`ascii_whitespaces`]: char::is_ascii_whitespace
+pub(super) fn leading_whitespaces(s: &str) -> &str {
+  let trimmed = s.trim_start_matches(|ch: char| ch.is_ascii_whitespace());
+  &s[..s.len() - trimmed.len()]
+}
+
+/// Returns the indentation aligning with the end of the line `prefix`, keeping
+/// its leading whitespaces and replacing the remaining characters with spaces.
+fn column_indent(prefix: &str) -> String {
+  let indent = leading_whitespaces(prefix);
+  let align = " ".repeat(prefix[indent.len()..].chars().count());
+  format!("{indent}{align}")
+}
+
+/// Returns the indentation `ws` moved from the base indentation `old` to
+/// `new`.
+///
+/// An indentation not based on `old` is shifted by the difference of length
+/// between both bases.
+fn rebase(ws: &str, old: &str, new: &str) -> String {
+  match ws.strip_prefix(old) {
+    Some(rest) => format!("{new}{rest}"),
+    None => match new.len().checked_sub(old.len()) {
+      Some(delta) => format!("{}{ws}", &new[..delta]),
+      None => ws[(old.len() - new.len()).min(ws.len())..].to_string(),
+    },
+  }
+}
+
+/// Returns whether a token of `node` starts at `byte`, that is, whether `byte`
+/// is not located within a token spanning multiple lines, such as a string or
+/// a comment, nor within a verbatim node.
+fn starts_token<'tree>(
+  node: &Node<'tree>,
+  byte: usize,
+  settings: &Settings<'_, 'tree>,
+) -> bool {
+  node.descendant_for_byte_range(byte, byte).is_some_and(|token| {
+    token.start_byte() == byte
+      && settings
+        .verbatim_ancestor(&token)
+        .is_none_or(|verbatim| verbatim.start_byte() == byte)
+  })
+}
+
+/// Moves the indentation of the lines `node` spans after its first one from
+/// the base indentation `old` to `new`.
+///
+/// Blank lines and lines starting within a token or a verbatim node are left
+/// alone.
+fn shift_lines<'tree>(
+  node: &Node<'tree>,
+  old: &str,
+  new: &str,
+  settings: &Settings<'_, 'tree>,
+  editor: &mut Editor,
+) -> Result<(), Error> {
+  if old == new {
+    return Ok(());
+  }
+
+  let src = editor.src();
+  let edits = (node.start_position().row + 1..=node.end_position().row)
+    .filter_map(|row| {
+      let line = src.line(row);
+      let line_byte_ix = src.line_to_byte(row);
+      let len = line.chars().take_while(|ch| matches!(ch, ' ' | '\t')).count();
+      let byte = line_byte_ix + len;
+      let blank = matches!(line.get_char(len), None | Some('\n'));
+      (!blank && byte < node.end_byte() && starts_token(node, byte, settings))
+        .then(|| {
+          let ws = line.slice(..len).to_string();
+          (line_byte_ix..byte, rebase(&ws, old, new))
+        })
+    })
+    .collect::<Vec<_>>();
+
+  edits.into_iter().try_for_each(|(range, ws)| {
+    editor.replace(range, &ws).map_err(Error::Edit)?;
+    Ok(())
+  })
+}
+
 /// Attempts to return a [`Node`] that is located prior to `node`.
 fn prev(mut node: Node<'_>) -> Option<Node<'_>> {
   loop {
@@ -24,6 +113,81 @@ fn prev(mut node: Node<'_>) -> Option<Node<'_>> {
   }
 }
 
+/// Returns the ancestors of `node`, from its parent up to the root.
+fn ancestors(node: Node<'_>) -> impl Iterator<Item = Node<'_>> {
+  iter::successors(node.parent(), Node::parent)
+}
+
+/// Returns the indentation of `node`, captured by `cap_ix`, according to
+/// `rule` and `style`.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn indentation(
+  query: &Query,
+  cap_ix: u32,
+  node: &Node<'_>,
+  rule: &IndentRule,
+  style: &str,
+  nodes_provider: &Provider<'_, '_>,
+  editor: &Editor,
+) -> Result<String, Error> {
+  let offset = |node_id| nodes_provider.node_for_id(node_id).copied();
+  Ok(
+    rule_indentation(node, rule, style, offset, editor)
+      .map_err(|msg| Error::cap(query, cap_ix, msg))?,
+  )
+}
+
+/// Returns the indentation of `node` according to `rule` and `style`, getting
+/// the node of an ID through `offset`, or why it cannot be computed.
+pub(super) fn rule_indentation<'tree>(
+  node: &Node<'tree>,
+  rule: &IndentRule,
+  style: &str,
+  offset: impl Fn(usize) -> Option<Node<'tree>>,
+  editor: &Editor,
+) -> std::result::Result<String, String> {
+  use IndentRule::*;
+  Ok(match rule {
+    Absolute(n) => style.repeat(*n as usize).to_string(),
+    Offset(node_id) => {
+      let offset = offset(*node_id)
+        .ok_or_else(|| format!("no offset node for {}", Displayer(node)))?;
+      column_indent(&editor.line_prefix(offset.start_byte(), Bias::Right))
+    }
+    Minus(anchor, n) | Plus(anchor, n) => {
+      let base = match &anchor.kind {
+        None => node.parent(),
+        Some(kind) => ancestors(*node).find(|node| node.kind() == kind),
+      }
+      .ok_or_else(|| {
+        let base = match &anchor.kind {
+          None => "parent node".to_string(),
+          Some(kind) => format!("ancestor of kind \"{kind}\""),
+        };
+        format!("no {base} for {}", Displayer(node))
+      })?;
+      let prefix = editor.line_prefix(base.start_byte(), Bias::Right);
+      let indent = match anchor.column {
+        false => leading_whitespaces(&prefix).to_string(),
+        true => column_indent(&prefix),
+      };
+      match rule {
+        Minus(..) => {
+          let len = style.len() * *n as usize;
+          let ix = indent.len().checked_sub(len).ok_or_else(|| {
+            format!(
+              "unable to indent {} with rule \"{rule}\"",
+              Displayer(node)
+            )
+          })?;
+          indent[..ix].to_string()
+        }
+        _ => format!("{indent}{}", style.repeat(*n as usize)),
+      }
+    }
+  })
+}
+
 pub struct Indent;
 
 impl Predicate for Indent {
@@ -51,9 +215,18 @@ impl Predicate for Indent {
       };
 
       for node in nodes_provider.nodes_for_cap_ix(cap_ix) {
+        if let Some(verbatim) = settings.verbatim_ancestor(node) {
+          log::trace!(
+            "not indenting {} within verbatim {}",
+            Displayer(node),
+            Displayer(&verbatim),
+          );
+          continue;
+        }
+
         let rule = match settings
           .for_node(node)
-          .and_then(|settings| settings.indent_rule())
+          .and_then(|settings| settings.indent_rule().cloned())
         {
           None => {
             log::warn!(
@@ -65,104 +238,161 @@ impl Predicate for Indent {
           Some(rule) => rule,
         };
 
-        let src = editor.src();
+        let indent = indentation(
+          query,
+          cap_ix,
+          node,
+          &rule,
+          style,
+          nodes_provider,
+          editor,
+        )?;
 
-        use crate::settings::IndentRule::*;
-        let indent = match rule {
-          Absolute(n) => style.repeat(n as usize).to_string(),
-          Offset(node_id) => {
-            let mut node = *nodes_provider.node_for_id(node_id).unwrap();
-            editor.sync(&mut node);
-            let row = node.start_position().row;
-            let whitespaces = ascii_whitespaces(row, src);
-            let row_ch_ix = src.line_to_char(row);
-            let offset = row_ch_ix + whitespaces;
-            let indent = src.slice(row_ch_ix..offset);
-            let start_ch = src.byte_to_char(node.start_byte());
-            let align = " ".repeat(start_ch - offset);
-            format!("{indent}{align}")
-          }
-          _ => {
-            let mut parent = node.parent().ok_or_else(|| {
-              Error::cap(
-                query,
-                cap_ix,
-                format!("no parent node for {}", Displayer(node)),
-              )
-            })?;
-            editor.sync(&mut parent);
-            let row = parent.start_position().row;
-            let whitespaces = ascii_whitespaces(row, src);
-            let row_ch_ix = src.line_to_char(row);
-            let indent = src.slice(row_ch_ix..row_ch_ix + whitespaces);
-            match rule {
-              Minus(n) => {
-                let len = style.len() * n as usize;
-                let ix =
-                  indent.len_bytes().checked_sub(len).ok_or_else(|| {
-                    Error::cap(
-                      query,
-                      cap_ix,
-                      format!(
-                        "unable to indent {} with rule \"{rule}\"",
-                        Displayer(node),
-                      ),
-                    )
-                  })?;
-                format!("{}", indent.slice(..ix))
-              }
-              Plus(n) => format!("{indent}{}", style.repeat(n as usize)),
-              _ => unreachable!(),
-            }
-          }
+        let old_indent = {
+          let src = editor.src();
+          let row_byte_ix = src.line_to_byte(node.start_position().row);
+          column_indent(
+            &src.byte_slice(row_byte_ix..node.start_byte()).to_string(),
+          )
         };
+        shift_lines(node, &old_indent, &indent, settings, editor)?;
 
-        let mut node = *node;
-        match prev(node) {
+        let (range, indent) = match prev(*node) {
           None => {
-            editor.sync(&mut node);
+            let src = editor.src();
             let row = node.start_position().row;
             let whitespaces = ascii_whitespaces(row, src);
             let row_byte_ix = src.line_to_byte(row);
-            editor.replace(
-              &Range {
-                start_byte: row_byte_ix,
-                end_byte: row_byte_ix + whitespaces,
-                start_point: Point { row, column: 0 },
-                end_point: Point { row, column: whitespaces },
-              },
-              &indent,
-            );
+            (row_byte_ix..row_byte_ix + whitespaces, indent)
           }
-          Some(mut prev) => {
-            editor.sync(&mut node);
-            editor.sync(&mut prev);
-            let node_start_point = node.start_position();
-            let prev_end_point = prev.end_position();
-            let (start_byte, start_point, indent) = match node_start_point.row
-              == prev_end_point.row
-            {
-              false => (
-                src.line_to_byte(node_start_point.row),
-                Point { row: node_start_point.row, column: 0 },
-                indent,
-              ),
-              true => (prev.end_byte(), prev_end_point, format!("\n{indent}")),
+          Some(prev) => {
+            let range = prev.end_byte()..node.start_byte();
+            let text = editor.text(range.clone());
+            let indent = match text.rfind('\n') {
+              None => format!("\n{indent}"),
+              Some(ix) => format!("{}{indent}", &text[..=ix]),
             };
-            editor.replace(
-              &Range {
-                start_byte,
-                end_byte: node.start_byte(),
-                start_point,
-                end_point: node.start_position(),
-              },
-              &indent,
-            );
+            (range, indent)
           }
-        }
+        };
+        editor.replace(range, &indent).map_err(Error::Edit)?;
       }
     }
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{predicates::spacer::Spacer, query_testing::prelude::*},
+  };
+
+  #[test]
+  fn indent_after_spacer() {
+    let res = cook_debugging(
+      "fn f() {foo( a );bar();}",
+      lines!(
+        r#"(#set! indent-style "  ")"#,
+        r#"((expression_statement) @stmt (#spacer! @stmt ""))"#,
+        "((block (expression_statement) @stmt)",
+        r#" (#set! @stmt indent-rule "+1")"#,
+        " (#indent! @stmt))",
+      ),
+      |_, _, _, _, _, _| {},
+      |setting_parsers| *setting_parsers = Parsers::builtin().unwrap(),
+      |predicates| {
+        predicates.push(Indent).unwrap();
+        predicates.push(Spacer).unwrap();
+      },
+    );
+    assert_eq!(res.unwrap(), "fn f() {\n  foo(a);\n  bar();}");
+  }
+
+  #[test]
+  fn indent_block() {
+    let res = cook_debugging(
+      lines!(
+        "fn f() {",
+        "foo(a,",
+        "    b);",
+        "",
+        "    let s = \"x",
+        "  y\";",
+        "    /* c",
+        "  */",
+        "}",
+      ),
+      lines!(
+        r#"(#set! indent-style "  ")"#,
+        "((block [(expression_statement) (let_declaration)] @stmt)",
+        r#" (#set! @stmt indent-rule "+1")"#,
+        " (#indent! @stmt))",
+      ),
+      |_, _, _, _, _, _| {},
+      |setting_parsers| *setting_parsers = Parsers::builtin().unwrap(),
+      |predicates| predicates.push(Indent).unwrap(),
+    );
+    assert_eq!(
+      res.unwrap(),
+      lines!(
+        "fn f() {",
+        "  foo(a,",
+        "      b);",
+        "",
+        "  let s = \"x",
+        "  y\";",
+        "    /* c",
+        "  */",
+        "}",
+      ),
+    );
+  }
+
+  #[test]
+  fn indent_anchors() {
+    let cook = |rule| {
+      cook_debugging(
+        "fn f() {\n  if x { foo(bar,\nbaz); }\n}",
+        &[
+          r#"(#set! indent-style "  ")"#,
+          "((arguments (identifier) (identifier) @arg)",
+          &format!(r#" (#set! @arg indent-rule "{rule}")"#),
+          " (#indent! @arg))",
+        ]
+        .join("\n"),
+        |_, _, _, _, _, _| {},
+        |setting_parsers| *setting_parsers = Parsers::builtin().unwrap(),
+        |predicates| predicates.push(Indent).unwrap(),
+      )
+      .unwrap()
+    };
+    let cooked = |n| {
+      format!("fn f() {{\n  if x {{ foo(bar,\n{}baz); }}\n}}", " ".repeat(n))
+    };
+    assert_eq!(cook("+1"), cooked(4));
+    assert_eq!(cook("^+0"), cooked(12));
+    assert_eq!(cook("block+1"), cooked(4));
+    assert_eq!(cook("block^+1"), cooked(9));
+    assert_eq!(cook("function_item-0"), cooked(0));
+  }
+
+  #[test]
+  fn indent_mid_line() {
+    let res = cook_debugging(
+      lines!("fn f() {foo(a,", "        b);", "}"),
+      lines!(
+        r#"(#set! indent-style "  ")"#,
+        "((block (expression_statement) @stmt)",
+        r#" (#set! @stmt indent-rule "+1")"#,
+        " (#indent! @stmt))",
+      ),
+      |_, _, _, _, _, _| {},
+      |setting_parsers| *setting_parsers = Parsers::builtin().unwrap(),
+      |predicates| predicates.push(Indent).unwrap(),
+    );
+    assert_eq!(res.unwrap(), lines!("fn f() {", "  foo(a,", "  b);", "}"));
+  }
+}
@@ -45,9 +45,9 @@ impl Predicate for IndentOffset {
 
     let rule = IndentRule::Offset(offset_node_id);
 
-    nodes_provider.nodes_for_cap_ix(cap_ix).into_iter().for_each(|node| {
+    nodes_provider.nodes_for_cap_ix(cap_ix).for_each(|node| {
       if let Some(old_rule) =
-        settings.node_entry(node).or_default().set_indent_rule(rule)
+        settings.node_entry(node).or_default().set_indent_rule(rule.clone())
       {
         log::warn!(
           "\"indent-rule\" overwritten for {}; old rule was \"{old_rule}\"",
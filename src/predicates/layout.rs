@@ -0,0 +1,226 @@
+use {
+  crate::{
+    editor::{Bias, Editor, Error as EditErr, Origin},
+    node_utils::IdToNode,
+    predicates::{
+      align::width,
+      indent::{leading_whitespaces, rule_indentation},
+    },
+    settings::{Cpl, Settings},
+  },
+  std::{cmp::Reverse, ops::Range},
+  tree_sitter::Node,
+};
+
+/// Node whose soft line breaks are all broken, unless it fits within `cpl`.
+pub(crate) struct Group<'tree> {
+  pub node: Node<'tree>,
+  pub cpl: Option<Cpl>,
+}
+
+/// Soft line break between the nodes `a` and `b`.
+pub(crate) struct Softline<'tree> {
+  pub a: Node<'tree>,
+  pub b: Node<'tree>,
+
+  /// Text separating both nodes when the break is not broken.
+  pub flat: String,
+
+  pub indent_style: Option<String>,
+
+  pub origin: Option<Origin>,
+}
+
+impl Softline<'_> {
+  #[inline]
+  fn gap(&self) -> Range<usize> { self.a.end_byte()..self.b.start_byte() }
+}
+
+/// Groups and soft line breaks requested by the patterns applied so far.
+#[derive(Default)]
+pub(crate) struct Layout<'tree> {
+  groups: Vec<Group<'tree>>,
+  softlines: Vec<Softline<'tree>>,
+}
+
+impl<'tree> Layout<'tree> {
+  #[inline]
+  pub fn push_group(&mut self, group: Group<'tree>) { self.groups.push(group) }
+
+  #[inline]
+  pub fn push_softline(&mut self, softline: Softline<'tree>) {
+    self.softlines.push(softline)
+  }
+
+  /// Returns the current text of `group` with the soft line breaks of
+  /// `softlines` located in it not broken.
+  fn flat(
+    group: &Node<'_>,
+    softlines: &[&Softline<'_>],
+    editor: &Editor,
+  ) -> String {
+    let text = editor.text(group.byte_range());
+    let base = editor.map_byte(group.start_byte(), Bias::Left);
+    let mut flat = String::new();
+    let mut ix = 0;
+    for softline in softlines {
+      let gap = softline.gap();
+      let start = editor.map_byte(gap.start, Bias::Left) - base;
+      let end = editor.map_byte(gap.end, Bias::Right) - base;
+      flat.push_str(&text[ix..start]);
+      flat.push_str(&softline.flat);
+      ix = end;
+    }
+    flat.push_str(&text[ix..]);
+    flat
+  }
+
+  /// Returns whether `group`, with the soft line breaks of `softlines` not
+  /// broken, fits within `cpl` up to the end of its first line.
+  fn fits(
+    group: &Group<'_>,
+    softlines: &[&Softline<'_>],
+    editor: &Editor,
+  ) -> bool {
+    let cpl = match group.cpl {
+      None | Some(Cpl::Unlimited) => return true,
+      Some(cpl) => u8::from(cpl) as usize,
+    };
+    let node = &group.node;
+    let flat = Self::flat(node, softlines, editor);
+    let mut len = width(&editor.line_prefix(node.start_byte(), Bias::Left));
+    match flat.split_once('\n') {
+      Some((line, _)) => len += width(line),
+      None => {
+        let src = editor.src();
+        let row = node.end_position().row;
+        let line_end = src.line_to_byte(row) + src.line(row).len_bytes();
+        let rest = editor.text(node.end_byte()..line_end);
+        len +=
+          width(&flat) + width(rest.split('\n').next().unwrap_or_default());
+      }
+    }
+    len <= cpl
+  }
+
+  /// Returns the text breaking `softline` of `group`, getting the node an
+  /// offset indentation rule aligns with from `id_to_node`.
+  fn broken(
+    group: &Node<'tree>,
+    softline: &Softline<'tree>,
+    settings: &Settings<'_, 'tree>,
+    id_to_node: &IdToNode<'tree>,
+    editor: &Editor,
+  ) -> String {
+    let style = softline.indent_style.as_deref().unwrap_or_default();
+    let b = &softline.b;
+    if let Some(rule) =
+      settings.for_node(b).and_then(|settings| settings.indent_rule())
+    {
+      let offset = |node_id| id_to_node.get(&node_id).copied();
+      match rule_indentation(b, rule, style, offset, editor) {
+        Ok(indent) => return format!("\n{indent}"),
+        Err(msg) => log::warn!("{msg}; indenting relative to group instead"),
+      }
+    }
+    let prefix = editor.line_prefix(group.start_byte(), Bias::Right);
+    let indent = leading_whitespaces(&prefix);
+    match b.end_byte() == group.end_byte() {
+      false => format!("\n{indent}{style}"),
+      true => format!("\n{indent}"),
+    }
+  }
+
+  /// Breaks the soft line breaks of the groups not fitting within their
+  /// `cpl`, from the outermost to the innermost, and the ones of their
+  /// descendants otherwise. Soft line breaks outside of any group are never
+  /// broken.
+  ///
+  /// A soft line break belongs to the innermost group containing it. Gaps
+  /// holding something else than whitespaces are left alone.
+  pub fn resolve(
+    mut self,
+    settings: &Settings<'_, 'tree>,
+    id_to_node: &IdToNode<'tree>,
+    editor: &mut Editor,
+  ) -> Result<(), (Option<Origin>, EditErr)> {
+    let src = editor.src();
+    self.softlines.retain(|softline| {
+      let gap = src.byte_slice(softline.gap());
+      let blank = gap.chars().all(|ch| ch.is_ascii_whitespace());
+      if !blank {
+        log::warn!("soft line break over non-whitespace {:?} skipped", gap);
+      }
+      blank && softline.a.end_byte() <= softline.b.start_byte()
+    });
+    self.softlines.sort_by_key(|softline| softline.a.end_byte());
+    self.softlines.dedup_by_key(|softline| softline.gap());
+    self.groups.sort_by_key(|group| {
+      (group.node.start_byte(), Reverse(group.node.end_byte()))
+    });
+    self.groups.dedup_by_key(|group| group.node.id());
+
+    let contains = |node: &Node<'_>, range: &Range<usize>| {
+      node.start_byte() <= range.start && range.end <= node.end_byte()
+    };
+    let owners = self
+      .softlines
+      .iter()
+      .map(|softline| {
+        self
+          .groups
+          .iter()
+          .rposition(|group| contains(&group.node, &softline.gap()))
+      })
+      .collect::<Vec<_>>();
+
+    let mut broken = vec![false; self.groups.len()];
+    let mut ancestors: Vec<usize> = Vec::new();
+    for (ix, group) in self.groups.iter().enumerate() {
+      while ancestors.last().is_some_and(|&parent| {
+        !contains(&self.groups[parent].node, &group.node.byte_range())
+      }) {
+        ancestors.pop();
+      }
+      let parent_broken =
+        ancestors.last().is_none_or(|&parent| broken[parent]);
+      let inner = self
+        .softlines
+        .iter()
+        .filter(|softline| contains(&group.node, &softline.gap()))
+        .collect::<Vec<_>>();
+      broken[ix] = parent_broken && !Self::fits(group, &inner, editor);
+      ancestors.push(ix);
+
+      for (softline, _) in self
+        .softlines
+        .iter()
+        .zip(&owners)
+        .filter(|(_, owner)| **owner == Some(ix))
+      {
+        let text = match broken[ix] {
+          false => softline.flat.clone(),
+          true => {
+            Self::broken(&group.node, softline, settings, id_to_node, editor)
+          }
+        };
+        editor.set_origin(softline.origin);
+        editor
+          .replace(softline.gap(), &text)
+          .map_err(|err| (softline.origin, err))?;
+      }
+    }
+
+    for (softline, _) in
+      self.softlines.iter().zip(&owners).filter(|(_, owner)| owner.is_none())
+    {
+      editor.set_origin(softline.origin);
+      editor
+        .replace(softline.gap(), &softline.flat)
+        .map_err(|err| (softline.origin, err))?;
+    }
+    editor.set_origin(None);
+
+    Ok(())
+  }
+}
@@ -1,6 +1,14 @@
+mod align;
+mod alternatives;
 mod err;
+mod gap;
+mod group;
 mod indent;
 mod indent_offset;
+mod layout;
+mod newline;
+mod select;
+mod softline;
 mod space;
 mod spacer;
 
@@ -9,21 +17,26 @@ mod debugger;
 
 #[cfg(test)]
 pub(crate) use debugger::Debugger;
-pub use err::Error;
 use {
   crate::{
     editor::Editor,
     node_utils::Provider,
+    registry::{Error as RegistryErr, Registry},
     settings::{Scope, Settings},
   },
   error_stack::Result,
-  fnv::FnvHashMap,
   tree_sitter::{Query, QueryPredicate, QueryPredicateArg},
 };
+pub(crate) use {align::width, alternatives::Alternatives, layout::Layout};
+pub use {err::Error, select::Selector};
 
-pub trait Predicate {
-  fn name(&self) -> &'static str;
+/// Predicate applied to the nodes of a match.
+pub trait Predicate: Send + Sync {
+  /// Returns the operator naming the predicate in queries, optionally
+  /// namespaced, as in `acme.align!`.
+  fn name(&self) -> &str;
 
+  /// Applies the predicate with `args` to the nodes from `nodes_provider`.
   fn parse<'a, 'tree>(
     &self,
     query: &Query,
@@ -35,14 +48,29 @@ pub trait Predicate {
   ) -> Result<(), Error>;
 }
 
-type PredicatesInner<'a> = FnvHashMap<&'a str, &'a dyn Predicate>;
+/// Registry of predicates, owning them.
+pub struct Predicates(Registry<Box<dyn Predicate>>);
 
-pub struct Predicates<'a>(PredicatesInner<'a>);
+impl Predicates {
+  /// Creates a registry without any predicate.
+  pub fn empty() -> Self { Self(Registry::empty()) }
 
-impl<'a> Predicates<'a> {
-  pub fn empty() -> Self { Self(Default::default()) }
+  /// Creates a registry of the builtin predicates.
+  pub fn builtin() -> std::result::Result<Self, RegistryErr> {
+    let mut predicates = Self::empty();
+    predicates.push(align::Align)?;
+    predicates.push(alternatives::AlternativesPredicate)?;
+    predicates.push(group::Group)?;
+    predicates.push(indent::Indent)?;
+    predicates.push(indent_offset::IndentOffset)?;
+    predicates.push(newline::Newline)?;
+    predicates.push(softline::Softline)?;
+    predicates.push(space::Space)?;
+    predicates.push(spacer::Spacer)?;
+    Ok(predicates)
+  }
 
-  pub fn parse<'b, 'tree>(
+  pub(crate) fn parse<'b, 'tree>(
     &self,
     query: &Query,
     query_predicate: &'b QueryPredicate,
@@ -62,32 +90,58 @@ impl<'a> Predicates<'a> {
     )
   }
 
+  /// Registers `predicate` under its name.
+  #[inline]
   pub fn push(
     &mut self,
-    predicate: &'a impl Predicate,
-  ) -> Option<&'a dyn Predicate> {
-    self.0.insert(predicate.name(), predicate)
+    predicate: impl Predicate + 'static,
+  ) -> std::result::Result<(), RegistryErr> {
+    self.push_boxed(Box::new(predicate))
   }
-}
 
-impl<'a> Default for Predicates<'a> {
-  fn default() -> Self {
-    let mut predicates = Predicates::empty();
+  /// Registers the boxed `predicate` under its name.
+  pub fn push_boxed(
+    &mut self,
+    predicate: Box<dyn Predicate>,
+  ) -> std::result::Result<(), RegistryErr> {
+    let name = predicate.name().to_string();
+    self.0.insert(&name, predicate)
+  }
 
-    macro_rules! insert {
-      ($($predicate:path),+ $(,)?) => {{
-        $(assert!(predicates.push(&$predicate).is_none());)+
-      }};
+  /// Replaces the predicate `name` by one without effect, returning whether
+  /// it was registered.
+  pub fn disable(&mut self, name: &str) -> bool {
+    match self.0.get_mut(name) {
+      None => false,
+      Some(predicate) => {
+        *predicate = Box::new(Disabled(name.to_string()));
+        true
+      }
     }
+  }
 
-    insert!(
-      indent::Indent,
-      indent_offset::IndentOffset,
-      space::Space,
-      spacer::Spacer,
-    );
+  /// Returns the names of the predicates in alphabetical order.
+  #[inline]
+  pub fn names(&self) -> impl Iterator<Item = &str> + '_ { self.0.names() }
+}
 
-    predicates
+/// Predicate without effect, in place of a disabled one.
+struct Disabled(String);
+
+impl Predicate for Disabled {
+  fn name(&self) -> &str { &self.0 }
+
+  fn parse<'a, 'tree>(
+    &self,
+    _query: &Query,
+    _args: &'a [QueryPredicateArg],
+    _scope: Scope,
+    _nodes_provider: &Provider<'_, 'tree>,
+    _settings: &mut Settings<'a, 'tree>,
+    _editor: &mut Editor,
+  ) -> Result<(), Error> {
+    log::trace!("skipping disabled predicate \"{}\"", self.0);
+    Ok(())
   }
 }
 
@@ -95,11 +149,11 @@ mod prelude {
   pub(super) use {
     super::{err::Error, Predicate},
     crate::{
-      editor::Editor,
+      editor::{Bias, Editor},
       node_utils::{Displayer, Provider, Walker},
       settings::{Scope, Settings},
     },
     error_stack::{bail, ensure, Result},
-    tree_sitter::{Node, Query, QueryPredicateArg, Range},
+    tree_sitter::{Node, Query, QueryPredicateArg},
   };
 }
@@ -0,0 +1,211 @@
+use {
+  crate::predicates::{
+    gap::{self, root, whitespace_runs},
+    indent::{indentation, leading_whitespaces},
+    prelude::*,
+    select::{captures, is_selector, pairs},
+  },
+  std::ops::RangeInclusive,
+};
+
+/// Returns the whitespace run `ws` with its number of newlines clamped to
+/// `bounds` and followed by `indent`.
+///
+/// A run followed by a token, such as a comment, keeps it on its line if it
+/// has no newline, and a run following a token keeps at least a newline if it
+/// has one, not to join a line comment with what follows it.
+fn clamp_newlines(
+  ws: &str,
+  bounds: &RangeInclusive<usize>,
+  before_token: bool,
+  after_token: bool,
+  indent: &str,
+) -> String {
+  let newlines = ws.matches('\n').count();
+  let mut min = match before_token {
+    false => *bounds.start(),
+    true => newlines.min(1),
+  };
+  if after_token && newlines > 0 {
+    min = min.max(1);
+  }
+  match newlines.clamp(min, (*bounds.end()).max(min)) {
+    0 if newlines == 0 => ws.to_string(),
+    0 => " ".to_string(),
+    n => format!("{}{indent}", "\n".repeat(n)),
+  }
+}
+
+pub struct Newline;
+
+impl Predicate for Newline {
+  fn name(&self) -> &'static str { "newline!" }
+
+  fn parse<'a, 'tree>(
+    &self,
+    query: &Query,
+    args: &'a [QueryPredicateArg],
+    _scope: Scope,
+    nodes_provider: &Provider<'_, 'tree>,
+    settings: &mut Settings<'a, 'tree>,
+    editor: &mut Editor,
+  ) -> Result<(), Error> {
+    let mut arg_ix = 0;
+    let mut args = args.iter().peekable();
+    let mut bound = || match args.peek() {
+      Some(arg @ QueryPredicateArg::String(s)) if !is_selector(arg) => {
+        args.next();
+        arg_ix += 1;
+        let ix = arg_ix - 1;
+        s.parse::<usize>()
+          .map(|n| Some((ix, n)))
+          .map_err(|_| Error::arg(ix, "usize boundary", format!("\"{s}\"")))
+      }
+      _ => Ok(None),
+    };
+    let min = bound()?.map_or(1, |(_, min)| min);
+    let max = match bound()? {
+      None => min,
+      Some((max_ix, max)) => {
+        ensure!(
+          min <= max,
+          Error::arg(max_ix, format!("boundary not less than {min}"), max),
+        );
+        max
+      }
+    };
+    let bounds = min..=max;
+
+    let captures = captures(args, arg_ix)?;
+    let b_cap_ix = captures.last().map_or(0, |(cap_ix, _)| *cap_ix);
+
+    for (a_node, b_node) in pairs(query, nodes_provider, &captures)? {
+      let gap = a_node.end_byte()..b_node.start_byte();
+      if gap.start > gap.end {
+        log::warn!(
+          "{} does not precede {}",
+          Displayer(a_node),
+          Displayer(b_node),
+        );
+        continue;
+      }
+
+      if gap::is_verbatim(a_node, b_node, settings) {
+        continue;
+      }
+
+      if b_node.is_extra() && !editor.text(gap.clone()).contains('\n') {
+        log::trace!("keeping trailing {} on its line", Displayer(b_node));
+        continue;
+      }
+
+      let indent = match (
+        settings.for_node(b_node).and_then(|settings| settings.indent_rule()),
+        settings.indent_style(),
+      ) {
+        (Some(rule), Some(style)) => indentation(
+          query,
+          b_cap_ix,
+          b_node,
+          rule,
+          style,
+          nodes_provider,
+          editor,
+        )?,
+        _ => {
+          let prefix = editor.line_prefix(a_node.start_byte(), Bias::Right);
+          leading_whitespaces(&prefix).to_string()
+        }
+      };
+
+      let (runs, _) = whitespace_runs(gap, &root(a_node), editor);
+      let last = runs.len() - 1;
+      for (ix, run) in runs.into_iter().enumerate() {
+        let ws = editor.text(run.clone());
+        let ws = clamp_newlines(&ws, &bounds, ix < last, ix > 0, &indent);
+        editor.replace(run, &ws).map_err(Error::Edit)?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::query_testing::prelude::*};
+
+  #[test]
+  fn clamp_newlines_() {
+    let clamp = |ws, before_token, after_token| {
+      clamp_newlines(ws, &(1..=2), before_token, after_token, "  ")
+    };
+    assert_eq!(clamp("", false, false), "\n  ");
+    assert_eq!(clamp(" \n\n\n\t", false, false), "\n\n  ");
+    assert_eq!(clamp(" ", true, false), " ");
+    assert_eq!(clamp("\n\n\n", true, true), "\n\n  ");
+    assert_eq!(clamp_newlines("\n", &(0..=0), false, true, ""), "\n");
+    assert_eq!(clamp_newlines("\n", &(0..=0), false, false, ""), " ");
+  }
+
+  #[test]
+  fn newline() {
+    let res = cook_debugging(
+      lines!(
+        "fn x_plus_y() -> u32 {",
+        "  let x = 5; let y = 11; // y",
+        "",
+        "",
+        "",
+        "      /* z",
+        "*/",
+        "",
+        "",
+        "",
+        "    x + y",
+        "}",
+      ),
+      lines!(
+        r#"(#set! indent-style "  ")"#,
+        "(function_item",
+        "  body: (block (_) @item . (_) @next)",
+        r#"  (#set! @next indent-rule "+1")"#,
+        "  (#newline! 1 2 @item @next))",
+      ),
+      |_, _, _, _, _, _| {},
+      |setting_parsers| *setting_parsers = Parsers::builtin().unwrap(),
+      |predicates| predicates.push(Newline).unwrap(),
+    );
+    assert_eq!(
+      res.unwrap(),
+      lines!(
+        "fn x_plus_y() -> u32 {",
+        "  let x = 5;",
+        "  let y = 11; // y",
+        "",
+        "  /* z",
+        "*/",
+        "",
+        "  x + y",
+        "}",
+      ),
+    );
+  }
+
+  #[test]
+  fn newline_bounds_err() {
+    let res = cook_debugging(
+      "fn f() {}",
+      "((function_item (identifier) @a (parameters) @b) (#newline! 2 1 @a \
+       @b))",
+      |_, _, _, _, _, _| {},
+      |_| {},
+      |predicates| predicates.push(Newline).unwrap(),
+    );
+    assert_eq!(
+      res.unwrap_err().current_context().to_string(),
+      "failed to apply predicate \"newline!\" from pattern #0: invalid \
+       argument #1; expected boundary not less than 2, got 1",
+    );
+  }
+}
@@ -0,0 +1,138 @@
+use {crate::predicates::prelude::*, std::str::FromStr};
+
+/// Members of the nodes of a quantified capture a predicate applies to.
+///
+/// In queries, a selector is a string argument preceding a capture, as in
+/// `(#space! ":last" @arg @close)`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Selector {
+  /// Every node.
+  #[default]
+  All,
+
+  /// The first node.
+  First,
+
+  /// Every node but the first and the last ones.
+  Inner,
+
+  /// The last node.
+  Last,
+}
+
+impl Selector {
+  /// Returns the members of `nodes` selected.
+  pub fn select<'n, 'tree>(
+    self,
+    nodes: &'n [Node<'tree>],
+  ) -> &'n [Node<'tree>] {
+    let len = nodes.len();
+    match self {
+      Self::All => nodes,
+      Self::First => &nodes[..len.min(1)],
+      Self::Inner => nodes.get(1..len.saturating_sub(1)).unwrap_or(&[]),
+      Self::Last => &nodes[len.saturating_sub(1)..],
+    }
+  }
+}
+
+impl FromStr for Selector {
+  type Err = ();
+
+  fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    match s {
+      ":first" => Ok(Self::First),
+      ":inner" => Ok(Self::Inner),
+      ":last" => Ok(Self::Last),
+      _ => Err(()),
+    }
+  }
+}
+
+/// Returns whether `arg` is a [`Selector`].
+#[inline]
+pub(super) fn is_selector(arg: &QueryPredicateArg) -> bool {
+  matches!(arg, QueryPredicateArg::String(s) if s.parse::<Selector>().is_ok())
+}
+
+/// Parses `args`, the first of which is the argument `arg_ix`, into captures,
+/// each optionally preceded by a [`Selector`].
+pub(super) fn captures<'a>(
+  args: impl Iterator<Item = &'a QueryPredicateArg>,
+  mut arg_ix: usize,
+) -> Result<Vec<(u32, Selector)>, Error> {
+  let mut captures = Vec::new();
+  let mut selector = None;
+  for arg in args {
+    match arg {
+      QueryPredicateArg::Capture(ix) => {
+        captures.push((*ix, selector.take().unwrap_or_default()));
+      }
+      QueryPredicateArg::String(s) => match (s.parse(), selector) {
+        (Ok(new), None) => selector = Some(new),
+        _ => bail!(Error::arg(arg_ix, "capture", format!("\"{s}\""))),
+      },
+    }
+    arg_ix += 1;
+  }
+  if selector.is_some() {
+    bail!(Error::arg(arg_ix, "capture", "none"));
+  }
+  Ok(captures)
+}
+
+/// Returns the pairs of nodes a predicate over `captures` applies to.
+///
+/// Those are the consecutive selected nodes of a single capture, or the
+/// selected nodes of two captures zipped together.
+pub(super) fn pairs<'p, 'tree>(
+  query: &Query,
+  nodes_provider: &'p Provider<'_, 'tree>,
+  captures: &[(u32, Selector)],
+) -> Result<Vec<(&'p Node<'tree>, &'p Node<'tree>)>, Error> {
+  let nodes = |(cap_ix, selector): (u32, Selector)| {
+    selector.select(nodes_provider.nodes_slice_for_cap_ix(cap_ix))
+  };
+  Ok(match *captures {
+    [a] => nodes(a).windows(2).map(|pair| (&pair[0], &pair[1])).collect(),
+    [a, b] => {
+      let (a_nodes, b_nodes) = (nodes(a), nodes(b));
+      if a_nodes.len() != b_nodes.len() {
+        log::warn!(
+          "\"{}\" selected {} nodes, but \"{}\" selected {}",
+          query.capture_names()[a.0 as usize],
+          a_nodes.len(),
+          query.capture_names()[b.0 as usize],
+          b_nodes.len(),
+        );
+      }
+      a_nodes.iter().zip(b_nodes).collect()
+    }
+    _ => bail!(Error::nargs("1 or 2 captures", captures.len())),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::query_testing::prelude::*;
+
+  #[test]
+  fn selectors() {
+    let cook = |args| {
+      cook_with(
+        "g(a, b, c, d);",
+        &format!(
+          "((arguments \"(\" @open (_) @arg (\",\" (_) @arg)* \")\" @close) \
+           (#newline! {args}))"
+        ),
+        |_| {},
+        Options::default(),
+      )
+      .unwrap()
+    };
+    assert_eq!(cook("@arg"), "g(a,\nb,\nc,\nd);");
+    assert_eq!(cook("\":inner\" @arg"), "g(a, b,\nc, d);");
+    assert_eq!(cook("\":first\" @open \":first\" @arg"), "g(\na, b, c, d);");
+    assert_eq!(cook("\":last\" @arg @close"), "g(a, b, c, d\n);");
+  }
+}
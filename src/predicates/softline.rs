@@ -0,0 +1,145 @@
+use crate::predicates::{layout, prelude::*};
+
+pub struct Softline;
+
+impl Predicate for Softline {
+  fn name(&self) -> &'static str { "softline!" }
+
+  fn parse<'a, 'tree>(
+    &self,
+    _query: &Query,
+    args: &'a [QueryPredicateArg],
+    _scope: Scope,
+    nodes_provider: &Provider<'_, 'tree>,
+    settings: &mut Settings<'a, 'tree>,
+    editor: &mut Editor,
+  ) -> Result<(), Error> {
+    let (flat, arg_ix) = match args.first() {
+      Some(QueryPredicateArg::String(s)) => (s.to_string(), 1),
+      _ => (String::new(), 0),
+    };
+
+    let mut cap_ixs =
+      args[arg_ix..].iter().enumerate().map(|(ix, arg)| match arg {
+        QueryPredicateArg::Capture(cap_ix) => Ok(*cap_ix),
+        QueryPredicateArg::String(s) => {
+          bail!(Error::arg(arg_ix + ix, "capture", format!("\"{s}\"")))
+        }
+      });
+    let (a_cap_ix, b_cap_ix) = match (cap_ixs.next(), cap_ixs.next()) {
+      (Some(a), Some(b)) => (a?, b?),
+      _ => bail!(Error::arg(arg_ix, "two captures", "less")),
+    };
+    ensure!(
+      cap_ixs.next().is_none(),
+      Error::arg(arg_ix + 2, "nothing", "an extra argument"),
+    );
+
+    let a_nodes = nodes_provider.nodes_for_cap_ix(a_cap_ix);
+    let b_nodes = nodes_provider.nodes_for_cap_ix(b_cap_ix);
+    for (a, b) in a_nodes.zip(b_nodes) {
+      let softline = layout::Softline {
+        a: *a,
+        b: *b,
+        flat: flat.clone(),
+        indent_style: settings.indent_style().map(str::to_string),
+        origin: editor.origin(),
+      };
+      settings.layout_mut().push_softline(softline);
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{predicates::group::Group, query_testing::prelude::*},
+  };
+
+  const SRC: &str = lines!(
+    "fn f() {",
+    "  foo(alpha, beta);",
+    "  foo(alpha, beta, gamma, delta, epsilon);",
+    "  foo(bar(alpha, beta), gamma, delta, eps);",
+    "}",
+  );
+
+  const QUERY: &str = lines!(
+    "(#set! cpl 32)",
+    r#"(#set! indent-style "  ")"#,
+    r#"((arguments "(" @open . (_) @first) (#softline! @open @first))"#,
+    r#"((arguments (_) @last . ")" @close) (#softline! @last @close))"#,
+    r#"((arguments "," @comma . (_) @next)"#,
+    r#" (#softline! " " @comma @next))"#,
+    "((arguments) @args (#group! @args))",
+  );
+
+  #[test]
+  fn softline() {
+    let res = cook_debugging(
+      SRC,
+      QUERY,
+      |_, _, _, _, _, _| {},
+      |setting_parsers| *setting_parsers = Parsers::builtin().unwrap(),
+      |predicates| {
+        predicates.push(Group).unwrap();
+        predicates.push(Softline).unwrap();
+      },
+    );
+    assert_eq!(
+      res.unwrap(),
+      lines!(
+        "fn f() {",
+        "  foo(alpha, beta);",
+        "  foo(",
+        "    alpha,",
+        "    beta,",
+        "    gamma,",
+        "    delta,",
+        "    epsilon",
+        "  );",
+        "  foo(",
+        "    bar(alpha, beta),",
+        "    gamma,",
+        "    delta,",
+        "    eps",
+        "  );",
+        "}",
+      ),
+    );
+  }
+
+  #[test]
+  fn softline_idempotent() {
+    let opts = Options { check_idempotent: true, ..Default::default() };
+    assert!(cook_with(SRC, QUERY, |_| {}, opts).is_ok());
+  }
+
+  #[test]
+  fn softline_offset_after() {
+    let query = lines!(
+      "(#set! cpl 32)",
+      r#"((arguments "," @comma . (_) @next) (#softline! " " @comma @next))"#,
+      "((arguments) @args (#group! @args))",
+      r#"((arguments "(" . (_) @first "," (_) @next)"#,
+      " (#indent-offset! @next @first))",
+    );
+    let src =
+      lines!("fn f() {", "  foo(alpha, beta, gamma, delta, epsilon);", "}",);
+    assert_eq!(
+      cook_with(src, query, |_| {}, Options::default()).unwrap(),
+      lines!(
+        "fn f() {",
+        "  foo(alpha,",
+        "      beta,",
+        "      gamma,",
+        "      delta,",
+        "      epsilon);",
+        "}",
+      ),
+    );
+  }
+}
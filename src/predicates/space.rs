@@ -1,5 +1,9 @@
 use {
-  crate::predicates::prelude::*,
+  crate::predicates::{
+    gap,
+    prelude::*,
+    select::{captures, is_selector, pairs},
+  },
   ropey::RopeSlice,
   std::{borrow::Cow, ops::RangeInclusive},
 };
@@ -54,14 +58,14 @@ impl Predicate for Space {
     args: &'a [QueryPredicateArg],
     _scope: Scope,
     nodes_provider: &Provider<'_, 'tree>,
-    _settings: &mut Settings<'a, 'tree>,
+    settings: &mut Settings<'a, 'tree>,
     editor: &mut Editor,
   ) -> Result<(), Error> {
     let mut arg_ix = 0;
     let mut args = args.iter().peekable();
 
     let sep = match args.peek() {
-      Some(QueryPredicateArg::String(s)) => {
+      Some(arg @ QueryPredicateArg::String(s)) if !is_selector(arg) => {
         args.next();
         arg_ix += 1;
         s.as_ref()
@@ -71,7 +75,7 @@ impl Predicate for Space {
 
     let noop = {
       let lower = match args.peek() {
-        Some(QueryPredicateArg::String(s)) => {
+        Some(arg @ QueryPredicateArg::String(s)) if !is_selector(arg) => {
           args.next();
           arg_ix += 1;
           let thresh = s.parse::<usize>().map_err(|_| {
@@ -83,7 +87,7 @@ impl Predicate for Space {
       };
 
       let upper = match args.peek() {
-        Some(QueryPredicateArg::String(s)) => {
+        Some(arg @ QueryPredicateArg::String(s)) if !is_selector(arg) => {
           args.next();
           arg_ix += 1;
           let thresh = s.parse::<usize>().map_err(|_| {
@@ -116,60 +120,13 @@ impl Predicate for Space {
       }
     }
 
-    let a_cap_ix = match args.next() {
-      None => bail!(Error::arg(arg_ix, "capture", "none")),
-      Some(QueryPredicateArg::Capture(ix)) => *ix,
-      Some(QueryPredicateArg::String(s)) => {
-        bail!(Error::arg(arg_ix, "capture", format!("\"{s}\"")))
-      }
-    };
-
-    let b_cap_ix = match args.next() {
-      None => bail!(Error::arg(arg_ix, "capture", "none")),
-      Some(QueryPredicateArg::Capture(ix)) => *ix,
-      Some(QueryPredicateArg::String(s)) => {
-        bail!(Error::arg(arg_ix, "capture", format!("\"{s}\"")));
-      }
-    };
-
-    let mut a_nodes = nodes_provider.nodes_for_cap_ix(a_cap_ix);
-    let mut b_nodes = nodes_provider.nodes_for_cap_ix(b_cap_ix);
-
-    loop {
-      let (mut a_node, mut b_node) = match (a_nodes.next(), b_nodes.next()) {
-        (None, None) => break,
-        (None, Some(_)) => {
-          log::warn!(
-            "\"{}\" did not capture, but \"{}\" did",
-            query.capture_names()[a_cap_ix as usize],
-            query.capture_names()[b_cap_ix as usize],
-          );
-          break;
-        }
-        (Some(_), None) => {
-          log::warn!(
-            "\"{}\" did capture, but \"{}\" did not",
-            query.capture_names()[a_cap_ix as usize],
-            query.capture_names()[b_cap_ix as usize],
-          );
-          break;
-        }
-        (Some(prev), Some(cur)) => (*prev, *cur),
-      };
-
-      editor.sync(&mut a_node);
-      editor.sync(&mut b_node);
-
-      let range = Range {
-        start_byte: a_node.end_byte(),
-        end_byte: b_node.start_byte(),
-        start_point: a_node.end_position(),
-        end_point: b_node.start_position(),
-      };
-
-      let s = editor.src().byte_slice(range.start_byte..range.end_byte);
-      if noop.as_ref().map_or(true, |thresh| should_space(sep, thresh, s)) {
-        editor.replace(&range, sep);
+    let captures = captures(args, arg_ix)?;
+    for (a_node, b_node) in pairs(query, nodes_provider, &captures)? {
+      let range = a_node.end_byte()..b_node.start_byte();
+      let s = editor.text(range.clone());
+      let s = RopeSlice::from(s.as_str());
+      if noop.as_ref().is_none_or(|thresh| should_space(sep, thresh, s)) {
+        gap::space(a_node, b_node, sep, settings, editor)?;
       }
     }
 
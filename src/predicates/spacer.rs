@@ -1,5 +1,5 @@
 use {
-  crate::predicates::{prelude::*, space::is_ascii_whitespace},
+  crate::predicates::{gap, prelude::*, space::is_ascii_whitespace},
   std::borrow::Cow,
 };
 
@@ -8,38 +8,32 @@ fn spacer<'tree>(
   sep: &str,
   settings: &Settings<'_, 'tree>,
   editor: &mut Editor,
-) {
+) -> Result<(), Error> {
+  if settings.verbatim_ancestor(&node).is_some() {
+    log::trace!("not spacing {} within verbatim node", Displayer(&node));
+    return Ok(());
+  }
+
   let mut cursor = node.walk();
-  let mut walker = Walker::from(&mut cursor).filter(|item| {
-    let node = item.node();
-    node.child_count() == 0
-      && node
-        .parent()
-        .and_then(|parent| {
-          settings.for_node(&parent).map(|settings| !settings.ignored())
-        })
-        .unwrap_or(true)
-  });
+  let mut walker = Walker::from(&mut cursor)
+    .with_opaque(|node| settings.is_verbatim(node))
+    .filter(|item| {
+      let node = item.node();
+      node.child_count() == 0 || settings.is_verbatim(node)
+    });
 
   let mut prev = match walker.next() {
-    None => return,
-    Some(item) => item.into(),
+    None => return Ok(()),
+    Some(item) => Node::from(item),
   };
-  editor.sync(&mut prev);
 
   for item in walker {
-    let mut node = item.into();
-    editor.sync(&mut node);
-    let range = Range {
-      start_byte: prev.end_byte(),
-      end_byte: node.start_byte(),
-      start_point: prev.end_position(),
-      end_point: node.start_position(),
-    };
-    editor.replace(&range, sep);
-    editor.sync_last(&mut node);
+    let node = Node::from(item);
+    gap::space(&prev, &node, sep, settings, editor)?;
     prev = node;
   }
+
+  Ok(())
 }
 
 pub struct Spacer;
@@ -85,10 +79,8 @@ impl Predicate for Spacer {
       true => log::trace!("spacing with \"{sep}\""),
     }
 
-    nodes_provider.nodes_for_cap_ix(cap_ix).for_each(|node| {
-      spacer(*node, &sep, settings, editor);
-    });
-
-    Ok(())
+    nodes_provider
+      .nodes_for_cap_ix(cap_ix)
+      .try_for_each(|node| spacer(*node, &sep, settings, editor))
   }
 }
@@ -1,13 +1,14 @@
 pub(crate) use {
   crate::{
-    cook,
+    editor::Editor,
+    formatter::cook,
     node_utils::Provider,
     predicates::{Debugger, Predicates},
     settings::{Parsers, Scope, Settings},
-    Editor,
     Error as CrateErr,
+    Options,
   },
-  error_stack::Result,
+  error_stack::{IntoReport, Result},
   ropey::{Rope, RopeSlice},
   tree_sitter::{Parser as TsParser, Query, QueryCursor, QueryPredicateArg},
   tree_sitter_rust::language as rs_lang,
@@ -29,15 +30,17 @@ pub fn cook_debugging<D, P, S>(
 ) -> Result<Rope, CrateErr>
 where
   D: Fn(
-    &Query,
-    &[QueryPredicateArg],
-    Scope,
-    &Provider<'_, '_>,
-    &Settings<'_, '_>,
-    &Editor,
-  ),
-  P: FnOnce(&mut Predicates<'_>),
-  S: FnOnce(&mut Parsers<'_>),
+      &Query,
+      &[QueryPredicateArg],
+      Scope,
+      &Provider<'_, '_>,
+      &Settings<'_, '_>,
+      &Editor,
+    ) + Send
+    + Sync
+    + 'static,
+  P: FnOnce(&mut Predicates),
+  S: FnOnce(&mut Parsers),
 {
   let mut ts_parser = TsParser::new();
   let mut query_cursor = QueryCursor::new();
@@ -45,16 +48,46 @@ where
   setting_parsers_fn(&mut setting_parsers);
   let mut predicates = Predicates::empty();
   let debugger = Debugger::new("dbg!", debugger_fn);
-  predicates.push(&debugger);
+  predicates.push(debugger).unwrap();
   predicates_fn(&mut predicates);
+  let query =
+    Query::new(rs_lang(), query_src).map_err(CrateErr::Query).report()?;
   cook(
     &mut ts_parser,
     RopeSlice::from(src),
     rs_lang(),
-    query_src,
+    &query,
     &mut query_cursor,
     &setting_parsers,
+    &[],
     &predicates,
+    Options::default(),
+  )
+}
+
+pub fn cook_with<P>(
+  src: &str,
+  query_src: &str,
+  predicates_fn: P,
+  opts: Options,
+) -> Result<Rope, CrateErr>
+where
+  P: FnOnce(&mut Predicates),
+{
+  let mut predicates = Predicates::builtin().unwrap();
+  predicates_fn(&mut predicates);
+  let query =
+    Query::new(rs_lang(), query_src).map_err(CrateErr::Query).report()?;
+  cook(
+    &mut TsParser::new(),
+    RopeSlice::from(src),
+    rs_lang(),
+    &query,
+    &mut QueryCursor::new(),
+    &Parsers::builtin().unwrap(),
+    &[],
+    &predicates,
+    opts,
   )
 }
 
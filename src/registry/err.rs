@@ -0,0 +1,12 @@
+/// Error of a registry.
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Error {
+  /// An entry is already registered under the name.
+  #[error("\"{0}\" is already registered")]
+  Collision(String),
+
+  /// The name is not made of query identifiers joined by the namespace
+  /// separator.
+  #[error("invalid name \"{0}\"")]
+  Name(String),
+}
@@ -0,0 +1,87 @@
+mod err;
+
+pub use err::Error;
+use std::collections::BTreeMap;
+
+/// Separator of the namespaces from the name of an entry, as in `acme.align!`.
+///
+/// Query identifiers cannot hold a `/`, hence the dot.
+pub const NAMESPACE_SEP: char = '.';
+
+/// Returns whether `ch` may be part of a query identifier.
+#[inline]
+fn is_ident_char(ch: char) -> bool {
+  ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '?' | '!')
+}
+
+/// Checks that `name` is made of query identifiers joined by
+/// [`NAMESPACE_SEP`].
+fn check_name(name: &str) -> Result<(), Error> {
+  match name
+    .split(NAMESPACE_SEP)
+    .all(|ident| !ident.is_empty() && ident.chars().all(is_ident_char))
+  {
+    false => Err(Error::Name(name.to_string())),
+    true => Ok(()),
+  }
+}
+
+/// Entries registered under unique names, listed in alphabetical order.
+pub(crate) struct Registry<T>(BTreeMap<String, T>);
+
+impl<T> Registry<T> {
+  #[inline]
+  pub fn empty() -> Self { Self(BTreeMap::default()) }
+
+  /// Registers `entry` under `name`, unless it is invalid or taken.
+  pub fn insert(&mut self, name: &str, entry: T) -> Result<(), Error> {
+    check_name(name)?;
+    match self.0.contains_key(name) {
+      false => {
+        self.0.insert(name.to_string(), entry);
+        Ok(())
+      }
+      true => Err(Error::Collision(name.to_string())),
+    }
+  }
+
+  #[inline]
+  pub fn get(&self, name: &str) -> Option<&T> { self.0.get(name) }
+
+  #[inline]
+  pub fn get_mut(&mut self, name: &str) -> Option<&mut T> {
+    self.0.get_mut(name)
+  }
+
+  #[inline]
+  pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+    self.0.keys().map(String::as_str)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn registry() {
+    let mut registry = Registry::empty();
+    assert!(registry.insert("align!", 0).is_ok());
+    assert!(registry.insert("acme.align!", 1).is_ok());
+    assert!(registry.insert("acme.team.align!", 2).is_ok());
+    assert!(matches!(
+      registry.insert("align!", 3),
+      Err(Error::Collision(name)) if name == "align!",
+    ));
+    for name in ["", "acme.", ".align!", "acme..align!", "acme/align!", "a b"]
+    {
+      assert!(matches!(registry.insert(name, 4), Err(Error::Name(_))));
+    }
+    assert_eq!(registry.get("acme.align!"), Some(&1));
+    assert_eq!(registry.names().collect::<Vec<_>>(), [
+      "acme.align!",
+      "acme.team.align!",
+      "align!",
+    ]);
+  }
+}
@@ -0,0 +1,44 @@
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Eq, thiserror::Error, PartialEq)]
+pub enum Error {
+  #[error("unknown align style \"{0}\"; expected \"spaces\" or \"tabs\"")]
+  Unknown(String),
+}
+
+/// Characters padding nodes into alignment.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AlignStyle {
+  /// Pads with spaces.
+  #[default]
+  Spaces,
+
+  /// Pads with tabs, reaching the next tab stop.
+  Tabs,
+}
+
+impl AlignStyle {
+  /// Number of columns between tab stops.
+  pub const TAB_WIDTH: usize = 8;
+}
+
+impl fmt::Display for AlignStyle {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", match self {
+      Self::Spaces => "spaces",
+      Self::Tabs => "tabs",
+    })
+  }
+}
+
+impl FromStr for AlignStyle {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "spaces" => Ok(Self::Spaces),
+      "tabs" => Ok(Self::Tabs),
+      _ => Err(Error::Unknown(s.to_string())),
+    }
+  }
+}
@@ -12,6 +12,7 @@ pub enum Error {
   TooBig,
 }
 
+/// Number of characters per line within a sane range.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct SaneValue(u8);
 
@@ -41,9 +42,13 @@ impl TryFrom<u8> for SaneValue {
   }
 }
 
+/// Maximum number of characters per line.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Cpl {
+  /// No maximum.
   Unlimited,
+
+  /// Maximum within a sane range.
   Sane(SaneValue),
 }
 
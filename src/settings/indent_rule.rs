@@ -2,12 +2,18 @@ use std::{fmt, num::ParseIntError, str::FromStr};
 
 #[derive(Debug, Eq, thiserror::Error, PartialEq)]
 pub enum Error {
+  #[error("anchor \"{0}\" given to absolute rule")]
+  Anchor(String),
+
   #[error("empty indent rule")]
   Empty,
 
-  #[error("invalid non-digit character \"{0}\" at index 1")]
+  #[error("invalid non-digit character \"{0}\" after operator")]
   NonDigit(char),
 
+  #[error("missing operator")]
+  NoOp,
+
   #[error("invalid operator \"{0}\"")]
   Op(char),
 
@@ -18,12 +24,46 @@ pub enum Error {
   Value,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// Node which a relative [`IndentRule`] is measured from.
+///
+/// It is written before the operator of the rule as an optional node kind,
+/// followed by `^` to measure from the column of the node.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Anchor {
+  /// Kind of the closest ancestor to measure from, instead of the parent.
+  pub kind: Option<String>,
+
+  /// Whether to measure from the start column of the node, instead of the
+  /// indentation of its start row.
+  pub column: bool,
+}
+
+impl fmt::Display for Anchor {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(kind) = &self.kind {
+      write!(f, "{kind}")?;
+    }
+    if self.column {
+      write!(f, "^")?;
+    }
+    Ok(())
+  }
+}
+
+/// Rule indenting a node.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum IndentRule {
+  /// Indents by a number of levels.
   Absolute(u8),
-  Minus(u8),
+
+  /// Indents by a number of levels less than the anchor.
+  Minus(Anchor, u8),
+
+  /// Aligns with the start of the node of an ID.
   Offset(usize),
-  Plus(u8),
+
+  /// Indents by a number of levels more than the anchor.
+  Plus(Anchor, u8),
 }
 
 impl fmt::Display for IndentRule {
@@ -31,9 +71,9 @@ impl fmt::Display for IndentRule {
     use IndentRule::*;
     write!(f, "{}", match self {
       Absolute(n) => format!("={n}"),
-      Minus(n) => format!("-{n}"),
+      Minus(anchor, n) => format!("{anchor}-{n}"),
       Offset(node_id) => format!("#{node_id}"),
-      Plus(n) => format!("+{n}"),
+      Plus(anchor, n) => format!("{anchor}+{n}"),
     })
   }
 }
@@ -42,19 +82,34 @@ impl FromStr for IndentRule {
   type Err = Error;
 
   fn from_str(s: &str) -> Result<Self, Self::Err> {
+    if s.is_empty() {
+      return Err(Error::Empty);
+    }
+    let kind_len = s
+      .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+      .unwrap_or(s.len());
+    let (kind, s) = s.split_at(kind_len);
+    let (column, s) = match s.strip_prefix('^') {
+      None => (false, s),
+      Some(s) => (true, s),
+    };
+    let anchor =
+      Anchor { kind: (!kind.is_empty()).then(|| kind.to_string()), column };
+
     let mut chars = s.chars();
-    let op = chars.next().ok_or(Error::Empty)?;
+    let op = chars.next().ok_or(Error::NoOp)?;
     match chars.next() {
       None => Err(Error::Value),
       Some(ch) if ch.is_ascii_digit() => Ok(()),
       Some(ch) => Err(Error::NonDigit(ch)),
     }?;
-    let n = s[1..].parse()?;
+    let n = s[op.len_utf8()..].parse()?;
     use IndentRule::*;
     Ok(match op {
-      '+' => Plus(n),
-      '-' => Minus(n),
-      '=' => Absolute(n),
+      '+' => Plus(anchor, n),
+      '-' => Minus(anchor, n),
+      '=' if anchor == Anchor::default() => Absolute(n),
+      '=' => return Err(Error::Anchor(anchor.to_string())),
       ch => return Err(Error::Op(ch)),
     })
   }
@@ -77,7 +132,7 @@ mod tests {
 
       ($op:literal => $enum_var:ident) => {{
         test!($op, "-1", Err(Error::NonDigit('-')));
-        test!($op, "0", Ok(IndentRule::$enum_var(0)));
+        test!($op, "0", Ok(IndentRule::$enum_var(.., 0)));
         test!($op, "", Err(Error::Value));
         test!(
           $op,
@@ -103,5 +158,22 @@ mod tests {
     test!("", Err(Error::Empty));
     test!("#", Err(Error::Value));
     test!("#0", Err(Error::Op('#')));
+    test!("block", Err(Error::NoOp));
+    test!("^", Err(Error::NoOp));
+    test!("block^=1", Err(Error::Anchor(anchor)) => anchor == "block^");
+
+    let anchor = |kind: Option<&str>, column| Anchor {
+      kind: kind.map(str::to_string),
+      column,
+    };
+    for (s, rule) in [
+      ("+1", IndentRule::Plus(anchor(None, false), 1)),
+      ("^+1", IndentRule::Plus(anchor(None, true), 1)),
+      ("block+1", IndentRule::Plus(anchor(Some("block"), false), 1)),
+      ("arguments^-2", IndentRule::Minus(anchor(Some("arguments"), true), 2)),
+    ] {
+      assert_eq!(s.parse::<IndentRule>(), Ok(rule.clone()));
+      assert_eq!(rule.to_string(), s);
+    }
   }
 }
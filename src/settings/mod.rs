@@ -1,31 +1,54 @@
+mod align_style;
 mod cpl;
-pub mod indent_rule;
+mod indent_rule;
+/// Parsers of the settings.
 pub mod parsers;
 
-pub use {
-  cpl::{Cpl, Error as CplErr},
-  indent_rule::IndentRule,
-  parsers::Parsers,
-};
 use {
+  crate::predicates::{Alternatives, Layout},
   fnv::FnvHashMap,
-  std::{collections::hash_map::Entry, fmt, marker::PhantomData},
+  std::{collections::hash_map::Entry, fmt, iter, marker::PhantomData, mem},
   tree_sitter::Node,
 };
+pub use {
+  align_style::AlignStyle,
+  cpl::Cpl,
+  indent_rule::{Anchor, IndentRule},
+  parsers::Parsers,
+};
 
 #[derive(Default)]
-pub struct Global<'a> {
+pub(crate) struct Global<'a> {
+  align_style: Option<AlignStyle>,
   cpl: Option<Cpl>,
+  directive_prefix: Option<&'a str>,
   indent_style: Option<&'a str>,
+  verbatim: Option<&'a str>,
 }
 
 impl<'a> Global<'a> {
+  #[inline]
+  pub fn align_style(&self) -> Option<AlignStyle> { self.align_style }
+
+  #[inline]
+  pub fn set_align_style(&mut self, style: AlignStyle) -> Option<AlignStyle> {
+    self.align_style.replace(style)
+  }
+
   #[inline]
   pub fn cpl(&self) -> Option<Cpl> { self.cpl }
 
   #[inline]
   pub fn set_cpl(&mut self, cpl: Cpl) -> Option<Cpl> { self.cpl.replace(cpl) }
 
+  #[inline]
+  pub fn directive_prefix(&self) -> Option<&'a str> { self.directive_prefix }
+
+  #[inline]
+  pub fn set_directive_prefix(&mut self, prefix: &'a str) -> Option<&'a str> {
+    self.directive_prefix.replace(prefix)
+  }
+
   #[inline]
   pub fn indent_style(&self) -> Option<&'a str> { self.indent_style }
 
@@ -33,16 +56,34 @@ impl<'a> Global<'a> {
   pub fn set_indent_style(&mut self, style: &'a str) -> Option<&'a str> {
     self.indent_style.replace(style)
   }
+
+  #[inline]
+  pub fn verbatim(&self) -> Option<&'a str> { self.verbatim }
+
+  #[inline]
+  pub fn set_verbatim(&mut self, kinds: &'a str) -> Option<&'a str> {
+    self.verbatim.replace(kinds)
+  }
 }
 
 #[derive(Default)]
-pub struct Local<'a> {
+pub(crate) struct Local<'a> {
+  align_style: Option<AlignStyle>,
   cpl: Option<Cpl>,
   ignore_query: Option<&'a str>,
   indent_style: Option<&'a str>,
+  verbatim: Option<&'a str>,
 }
 
 impl<'a> Local<'a> {
+  #[inline]
+  pub fn align_style(&self) -> Option<AlignStyle> { self.align_style }
+
+  #[inline]
+  pub fn set_align_style(&mut self, style: AlignStyle) -> Option<AlignStyle> {
+    self.align_style.replace(style)
+  }
+
   #[inline]
   pub fn cpl(&self) -> Option<Cpl> { self.cpl }
 
@@ -53,8 +94,8 @@ impl<'a> Local<'a> {
   pub fn ignore_query(&self) -> Option<&'a str> { self.ignore_query }
 
   #[inline]
-  pub fn set_ignore_query(&mut self, style: &'a str) -> Option<&'a str> {
-    self.ignore_query.replace(style)
+  pub fn set_ignore_query(&mut self, query: &'a str) -> Option<&'a str> {
+    self.ignore_query.replace(query)
   }
 
   #[inline]
@@ -64,19 +105,31 @@ impl<'a> Local<'a> {
   pub fn set_indent_style(&mut self, style: &'a str) -> Option<&'a str> {
     self.indent_style.replace(style)
   }
+
+  #[inline]
+  pub fn verbatim(&self) -> Option<&'a str> { self.verbatim }
+
+  #[inline]
+  pub fn set_verbatim(&mut self, kinds: &'a str) -> Option<&'a str> {
+    self.verbatim.replace(kinds)
+  }
 }
 
-#[derive(Default)]
+/// Settings of a node.
+#[derive(Clone, Default)]
 pub struct NodeSettings<'tree> {
   ignored: bool,
   indent_rule: Option<IndentRule>,
+  verbatim: bool,
   _phantom: PhantomData<&'tree ()>,
 }
 
 impl<'tree> NodeSettings<'tree> {
+  /// Returns whether the node is ignored.
   #[inline]
   pub fn ignored(&self) -> bool { self.ignored }
 
+  /// Sets whether the node is ignored, returning the old value.
   #[inline]
   pub fn ignore(&mut self, cond: bool) -> bool {
     let old_val = self.ignored;
@@ -84,17 +137,40 @@ impl<'tree> NodeSettings<'tree> {
     old_val
   }
 
+  /// Returns the indentation rule of the node.
   #[inline]
-  pub fn indent_rule(&self) -> Option<IndentRule> { self.indent_rule }
+  pub fn indent_rule(&self) -> Option<&IndentRule> {
+    self.indent_rule.as_ref()
+  }
 
+  /// Sets the indentation rule of the node, returning the old one.
   #[inline]
   pub fn set_indent_rule(&mut self, rule: IndentRule) -> Option<IndentRule> {
     self.indent_rule.replace(rule)
   }
+
+  /// Returns whether the node is verbatim.
+  #[inline]
+  pub fn verbatim(&self) -> bool { self.verbatim }
+
+  /// Sets whether the node is verbatim, returning the old value.
+  #[inline]
+  pub fn set_verbatim(&mut self, cond: bool) -> bool {
+    mem::replace(&mut self.verbatim, cond)
+  }
 }
 
+/// Settings of the nodes by ID.
+///
+/// Entries accessed after a [`checkpoint`] are journaled along with their
+/// prior settings, so that they can be rolled back.
+///
+/// [`checkpoint`]: NodeToSettings::checkpoint
 #[derive(Default)]
-pub struct NodeToSettings<'tree>(FnvHashMap<usize, NodeSettings<'tree>>);
+pub(crate) struct NodeToSettings<'tree> {
+  map: FnvHashMap<usize, NodeSettings<'tree>>,
+  journal: Option<FnvHashMap<usize, Option<NodeSettings<'tree>>>>,
+}
 
 impl<'tree> NodeToSettings<'tree> {
   #[inline]
@@ -102,18 +178,43 @@ impl<'tree> NodeToSettings<'tree> {
     &mut self,
     node: &Node<'tree>,
   ) -> Entry<'_, usize, NodeSettings<'tree>> {
-    self.0.entry(node.id())
+    let id = node.id();
+    if let Some(journal) = &mut self.journal {
+      journal.entry(id).or_insert_with(|| self.map.get(&id).cloned());
+    }
+    self.map.entry(id)
   }
 
   #[inline]
   pub fn get(&self, node: &Node<'tree>) -> Option<&NodeSettings<'tree>> {
-    self.0.get(&node.id())
+    self.map.get(&node.id())
+  }
+
+  /// Starts journaling the entries accessed, forgetting those journaled so
+  /// far.
+  #[inline]
+  pub fn checkpoint(&mut self) { self.journal = Some(FnvHashMap::default()) }
+
+  /// Restores the entries accessed since the last [`checkpoint`], and stops
+  /// journaling them.
+  ///
+  /// [`checkpoint`]: NodeToSettings::checkpoint
+  pub fn rollback(&mut self) {
+    let journal = self.journal.take().unwrap_or_default();
+    journal.into_iter().for_each(|(id, settings)| match settings {
+      None => _ = self.map.remove(&id),
+      Some(settings) => _ = self.map.insert(id, settings),
+    });
   }
 }
 
+/// Scope of a pattern, and of the settings it sets.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Scope {
+  /// Scope of a non-rooted pattern, whose settings last for the whole query.
   Global,
+
+  /// Scope of a rooted pattern, whose settings last for one of its matches.
   Local,
 }
 
@@ -126,19 +227,46 @@ impl fmt::Display for Scope {
   }
 }
 
+/// Settings set by the patterns applied so far.
+///
+/// Local settings take precedence over global ones.
 #[derive(Default)]
 pub struct Settings<'a, 'tree> {
+  alternatives: Vec<Alternatives<'tree>>,
   global: Global<'a>,
+  ignored: Vec<Node<'tree>>,
+  layout: Layout<'tree>,
   local: Local<'a>,
   node_to_settings: NodeToSettings<'tree>,
 }
 
 impl<'a, 'tree> Settings<'a, 'tree> {
+  /// Returns the characters padding nodes into alignment.
+  #[inline]
+  pub fn align_style(&self) -> Option<AlignStyle> {
+    self.local.align_style().or_else(|| self.global.align_style())
+  }
+
+  #[inline]
+  fn set_align_style(
+    &mut self,
+    style: AlignStyle,
+    scope: Scope,
+  ) -> Option<AlignStyle> {
+    match scope {
+      Scope::Global => self.global.set_align_style(style),
+      Scope::Local => self.local.set_align_style(style),
+    }
+  }
+
+  /// Returns the maximum number of characters per line.
   #[inline]
   pub fn cpl(&self) -> Option<Cpl> {
     self.local.cpl().or_else(|| self.global.cpl())
   }
 
+  /// Sets the maximum number of characters per line within `scope`,
+  /// returning the old one.
   #[inline]
   pub fn set_cpl(&mut self, cpl: Cpl, scope: Scope) -> Option<Cpl> {
     match scope {
@@ -147,6 +275,18 @@ impl<'a, 'tree> Settings<'a, 'tree> {
     }
   }
 
+  /// Returns the prefix of comment directives.
+  #[inline]
+  pub fn directive_prefix(&self) -> Option<&'a str> {
+    self.global.directive_prefix()
+  }
+
+  #[inline]
+  fn set_directive_prefix(&mut self, prefix: &'a str) -> Option<&'a str> {
+    self.global.set_directive_prefix(prefix)
+  }
+
+  /// Returns the indentation style.
   #[inline]
   pub fn indent_style(&self) -> Option<&'a str> {
     self.local.indent_style().or_else(|| self.global.indent_style())
@@ -164,11 +304,49 @@ impl<'a, 'tree> Settings<'a, 'tree> {
     }
   }
 
+  /// Returns the query whose captures within the current match are ignored.
+  #[inline]
+  pub fn ignore_query(&self) -> Option<&'a str> { self.local.ignore_query() }
+
+  #[inline]
+  fn set_ignore_query(&mut self, query: &'a str) -> Option<&'a str> {
+    self.local.set_ignore_query(query)
+  }
+
+  /// Returns whether `node` is verbatim, either by itself or by its kind.
+  ///
+  /// Predicates treat a verbatim node as a single token, never editing inside
+  /// of it.
+  pub fn is_verbatim(&self, node: &Node<'tree>) -> bool {
+    self.for_node(node).is_some_and(NodeSettings::verbatim)
+      || [self.local.verbatim(), self.global.verbatim()]
+        .into_iter()
+        .flatten()
+        .any(|kinds| kinds.split_whitespace().any(|kind| kind == node.kind()))
+  }
+
+  /// Returns the outermost verbatim ancestor of `node`, if any.
+  pub fn verbatim_ancestor(&self, node: &Node<'tree>) -> Option<Node<'tree>> {
+    iter::successors(node.parent(), Node::parent)
+      .filter(|node| self.is_verbatim(node))
+      .last()
+  }
+
+  #[inline]
+  fn set_verbatim(&mut self, kinds: &'a str, scope: Scope) -> Option<&'a str> {
+    match scope {
+      Scope::Global => self.global.set_verbatim(kinds),
+      Scope::Local => self.local.set_verbatim(kinds),
+    }
+  }
+
+  /// Returns the settings of `node`, if any.
   #[inline]
   pub fn for_node(&self, node: &Node<'tree>) -> Option<&NodeSettings<'tree>> {
     self.node_to_settings.get(node)
   }
 
+  /// Returns the entry of the settings of `node`.
   #[inline]
   pub fn node_entry(
     &mut self,
@@ -177,6 +355,61 @@ impl<'a, 'tree> Settings<'a, 'tree> {
     self.node_to_settings.entry(node)
   }
 
+  /// Starts journaling the settings of the nodes, so that
+  /// [`rollback_nodes`] restores them.
+  ///
+  /// [`rollback_nodes`]: Settings::rollback_nodes
+  #[inline]
+  pub(crate) fn checkpoint_nodes(&mut self) {
+    self.node_to_settings.checkpoint()
+  }
+
+  /// Restores the settings of the nodes changed since [`checkpoint_nodes`].
+  ///
+  /// [`checkpoint_nodes`]: Settings::checkpoint_nodes
+  #[inline]
+  pub(crate) fn rollback_nodes(&mut self) { self.node_to_settings.rollback() }
+
+  /// Marks `node` as ignored, returning whether it already was.
+  ///
+  /// The node is pending until [`take_ignored`] hands it to the editor.
+  ///
+  /// [`take_ignored`]: Settings::take_ignored
+  pub(crate) fn ignore(&mut self, node: &Node<'tree>) -> bool {
+    self.ignored.push(*node);
+    self.node_entry(node).or_default().ignore(true)
+  }
+
+  /// Takes the nodes ignored since the last call.
+  #[inline]
+  pub(crate) fn take_ignored(&mut self) -> Vec<Node<'tree>> {
+    mem::take(&mut self.ignored)
+  }
+
+  /// Returns the alternatives requested so far.
+  #[inline]
+  pub(crate) fn alternatives_mut(&mut self) -> &mut Vec<Alternatives<'tree>> {
+    &mut self.alternatives
+  }
+
+  /// Takes the alternatives requested so far, leaving none.
+  #[inline]
+  pub(crate) fn take_alternatives(&mut self) -> Vec<Alternatives<'tree>> {
+    mem::take(&mut self.alternatives)
+  }
+
+  /// Returns the layout requested so far.
+  #[inline]
+  pub(crate) fn layout_mut(&mut self) -> &mut Layout<'tree> {
+    &mut self.layout
+  }
+
+  /// Takes the layout requested so far, leaving an empty one.
+  #[inline]
+  pub(crate) fn take_layout(&mut self) -> Layout<'tree> {
+    mem::take(&mut self.layout)
+  }
+
   #[inline]
-  pub fn reset(&mut self) { self.local = Default::default(); }
+  pub(crate) fn reset(&mut self) { self.local = Default::default(); }
 }
@@ -0,0 +1,33 @@
+use crate::settings::{align_style::AlignStyle, parsers::prelude::*};
+
+pub struct AlignStyleParser;
+
+impl Parser for AlignStyleParser {
+  fn setting(&self) -> &'static str { "align-style" }
+
+  fn parse<'a, 'tree>(
+    &self,
+    query_prop: &'a QueryProperty,
+    scope: Scope,
+    _nodes_provider: &Provider<'_, 'tree>,
+    settings: &mut Settings<'a, 'tree>,
+  ) -> Result<(), Error> {
+    ensure!(query_prop.capture_id.is_none(), Error::CapExtra);
+
+    let s = query_prop
+      .value
+      .as_ref()
+      .ok_or_else(|| Error::value("align style", "no value"))?;
+
+    let style = s.parse::<AlignStyle>().map_err(Error::other)?;
+
+    if let Some(old_style) = settings.set_align_style(style, scope) {
+      log::warn!(
+        "\"align-style\" overwritten {scope}ly; old style was \"{old_style}\"",
+      );
+    }
+
+    log::trace!("{scope}ly set \"align-style\" to \"{style}\"");
+    Ok(())
+  }
+}
@@ -37,7 +37,7 @@ impl Parser for CplParser {
 mod tests {
   use {
     super::*,
-    crate::{query_testing::prelude::*, settings::CplErr},
+    crate::{query_testing::prelude::*, settings::cpl::Error as CplErr},
     std::num::IntErrorKind,
   };
 
@@ -49,20 +49,22 @@ mod tests {
   ) -> Result<Rope, CrateErr>
   where
     F: Fn(
-      &Query,
-      &[QueryPredicateArg],
-      Scope,
-      &Provider<'_, '_>,
-      &Settings<'_, '_>,
-      &Editor,
-    ),
+        &Query,
+        &[QueryPredicateArg],
+        Scope,
+        &Provider<'_, '_>,
+        &Settings<'_, '_>,
+        &Editor,
+      ) + Send
+      + Sync
+      + 'static,
   {
     cook_debugging(
       src,
       query_src,
       debugger_fn,
       |setting_parsers| {
-        setting_parsers.push(&CplParser);
+        setting_parsers.push(CplParser).unwrap();
       },
       |_| {},
     )
@@ -158,7 +160,7 @@ mod tests {
         match label {
           "empty-cpl" => {
             assert_eq!(scope, Scope::Global);
-            assert!(matches!(settings.cpl(), None));
+            assert!(settings.cpl().is_none());
           }
           "with-cpl" => {
             assert_eq!(scope, Scope::Global);
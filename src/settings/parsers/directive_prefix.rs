@@ -0,0 +1,34 @@
+use crate::settings::parsers::prelude::*;
+
+// The formatter reads the prefix set by the query ahead of any pattern, and
+// otherwise the one of the default settings, applied before directives.
+pub struct DirectivePrefix;
+
+impl Parser for DirectivePrefix {
+  fn setting(&self) -> &'static str { "directive-prefix" }
+
+  fn parse<'a, 'tree>(
+    &self,
+    query_prop: &'a QueryProperty,
+    scope: Scope,
+    _nodes_provider: &Provider<'_, 'tree>,
+    settings: &mut Settings<'a, 'tree>,
+  ) -> Result<(), Error> {
+    ensure!(query_prop.capture_id.is_none(), Error::CapExtra);
+    ensure!(
+      scope == Scope::Global,
+      Error::other("\"directive-prefix\" only allowed in non-rooted patterns"),
+    );
+
+    let prefix = query_prop
+      .value
+      .as_ref()
+      .filter(|prefix| !prefix.trim().is_empty())
+      .ok_or_else(|| Error::value("directive prefix", "no value"))?;
+
+    settings.set_directive_prefix(prefix);
+    log::trace!("set \"directive-prefix\" to \"{prefix}\"");
+
+    Ok(())
+  }
+}
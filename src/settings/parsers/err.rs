@@ -1,34 +1,48 @@
 use std::string::ToString;
 
-#[derive(Debug, thiserror::Error)]
+/// Error of a setting [`Parser`].
+///
+/// [`Parser`]: crate::settings::parsers::Parser
+#[derive(Clone, Debug, thiserror::Error)]
 pub enum Error {
+  /// A capture was given where none is allowed.
   #[error("capture not allowed")]
   CapExtra,
 
+  /// No capture was given where one is required.
   #[error("missing capture")]
   CapMissing,
 
+  /// No parser is named after the key.
   #[error("invalid setting key \"{0}\"")]
   Key(String),
 
+  /// A node was matched where none is allowed.
   #[error("node not allowed")]
   NodeExtra,
 
+  /// No node was matched where one is required.
   #[error("missing node")]
   NodeMissing,
 
+  /// Any other error.
   #[error("{0}")]
   Other(String),
 
+  /// The value is not the `expected` one.
+  #[allow(missing_docs)]
   #[error("invalid value; expected {expected}, got {got}")]
   Value { expected: String, got: String },
 }
 
 impl Error {
+  /// Creates an [`Error::Key`].
   pub fn key(s: impl ToString) -> Self { Self::Key(s.to_string()) }
 
+  /// Creates an [`Error::Other`].
   pub fn other(t: impl ToString) -> Self { Self::Other(t.to_string()) }
 
+  /// Creates an [`Error::Value`].
   pub fn value(expected: impl ToString, got: impl ToString) -> Self {
     Self::Value { expected: expected.to_string(), got: got.to_string() }
   }
@@ -0,0 +1,81 @@
+use crate::settings::parsers::prelude::*;
+
+pub struct IgnoreQuery;
+
+impl Parser for IgnoreQuery {
+  fn setting(&self) -> &'static str { "ignore-query" }
+
+  fn parse<'a, 'tree>(
+    &self,
+    query_prop: &'a QueryProperty,
+    scope: Scope,
+    _nodes_provider: &Provider<'_, 'tree>,
+    settings: &mut Settings<'a, 'tree>,
+  ) -> Result<(), Error> {
+    ensure!(query_prop.capture_id.is_none(), Error::CapExtra);
+    ensure!(
+      scope == Scope::Local,
+      Error::other("\"ignore-query\" only allowed in rooted patterns"),
+    );
+
+    let query = query_prop
+      .value
+      .as_ref()
+      .ok_or_else(|| Error::value("query", "no value"))?;
+
+    if let Some(old_query) = settings.set_ignore_query(query) {
+      log::warn!(
+        "\"ignore-query\" overwritten; old query was \"{old_query}\""
+      );
+    }
+
+    log::trace!("set \"ignore-query\" to \"{query}\"");
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::query_testing::prelude::*};
+
+  #[test]
+  fn ignore_query() {
+    let res = cook_with(
+      "fn f() {\nlet  a  =  1;\nlet  b  =  g!(1,2);\n}",
+      lines!(
+        "((block) @block",
+        r#" (#set! ignore-query "(macro_invocation) @m")"#,
+        " (#spacer! @block))",
+      ),
+      |_| {},
+      Options::default(),
+    );
+    assert_eq!(res.unwrap(), "fn f() { let a = 1 ; let b = g!(1,2) ; }");
+  }
+
+  #[test]
+  fn ignore_query_err() {
+    let res = cook_with(
+      "fn f() {}",
+      r#"((block) @block (#set! ignore-query "(nope) @n"))"#,
+      |_| {},
+      Options::default(),
+    );
+    assert!(matches!(res.unwrap_err().current_context(), CrateErr::Setting {
+      cause: Error::Value { .. },
+      ..
+    },));
+
+    let res = cook_with(
+      "fn f() {}",
+      r#"(#set! ignore-query "(block) @b")"#,
+      |_| {},
+      Options::default(),
+    );
+    assert!(matches!(res.unwrap_err().current_context(), CrateErr::Setting {
+      cause: Error::Other(_),
+      ..
+    },));
+  }
+}
@@ -20,7 +20,7 @@ impl Parser for Ignored {
 
     nodes_provider.nodes_for_cap_ix(cap_ix.try_into().unwrap()).for_each(
       |node| {
-        if settings.node_entry(node).or_default().ignore(true) {
+        if settings.ignore(node) {
           log::warn!("\"ignored\" overwritten for {}", NodeDisplayer(node));
         }
         log::trace!("ignoring {}", NodeDisplayer(node));
@@ -43,20 +43,22 @@ mod tests {
   ) -> Result<Rope, CrateErr>
   where
     F: Fn(
-      &Query,
-      &[QueryPredicateArg],
-      Scope,
-      &Provider<'_, '_>,
-      &Settings<'_, '_>,
-      &Editor,
-    ),
+        &Query,
+        &[QueryPredicateArg],
+        Scope,
+        &Provider<'_, '_>,
+        &Settings<'_, '_>,
+        &Editor,
+      ) + Send
+      + Sync
+      + 'static,
   {
     cook_debugging(
       src,
       query_src,
       debugger_fn,
       |setting_parsers| {
-        setting_parsers.push(&Ignored);
+        setting_parsers.push(Ignored).unwrap();
       },
       |_| {},
     )
@@ -96,6 +98,29 @@ mod tests {
     assert!(res.is_ok());
   }
 
+  #[test]
+  fn ignored_subtree() {
+    let res = cook_with(
+      "fn f() {\nlet  a  =  1;\nlet  b  =  [1,\n    2];\n}",
+      lines!(
+        r#"(#set! indent-style "  ")"#,
+        "((let_declaration) @let (#spacer! @let))",
+        "((block (let_declaration) @let)",
+        r#" (#set! @let indent-rule "+1")"#,
+        " (#indent! @let))",
+        "((let_declaration pattern: (identifier) @id) @let",
+        r#" (#eq? @id "b")"#,
+        " (#set! @let ignored))",
+      ),
+      |_| {},
+      Options::default(),
+    );
+    assert_eq!(
+      res.unwrap(),
+      "fn f() {\n  let a = 1 ;\n  let  b  =  [1,\n    2];\n}",
+    );
+  }
+
   #[test]
   fn ignored_err_cap_missing() {
     let res =
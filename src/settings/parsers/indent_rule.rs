@@ -24,7 +24,7 @@ impl Parser for IndentRuleParser {
     nodes_provider.nodes_for_cap_ix(cap_ix.try_into().unwrap()).for_each(
       |node| {
         if let Some(old_rule) =
-          settings.node_entry(node).or_default().set_indent_rule(rule)
+          settings.node_entry(node).or_default().set_indent_rule(rule.clone())
         {
           log::warn!(
             "\"indent-rule\" overwritten for {}; old rule was \"{old_rule}\"",
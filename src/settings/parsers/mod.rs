@@ -1,23 +1,32 @@
+mod align_style;
 mod cpl;
+mod directive_prefix;
 mod err;
+mod ignore_query;
 mod ignored;
 mod indent_rule;
 mod indent_style;
+mod verbatim;
 
 pub use err::Error;
 use {
   crate::{
     node_utils::Provider,
+    registry::{Error as RegistryErr, Registry},
     settings::{Scope, Settings},
   },
   error_stack::Result,
-  fnv::FnvHashMap,
+  std::sync::Arc,
   tree_sitter::QueryProperty,
 };
 
-pub trait Parser {
-  fn setting(&self) -> &'static str;
+/// Parser of a setting from query properties.
+pub trait Parser: Send + Sync {
+  /// Returns the key of the setting in queries, optionally namespaced, as in
+  /// `acme.width`.
+  fn setting(&self) -> &str;
 
+  /// Parses `query_prop` into `settings`.
   fn parse<'a, 'tree>(
     &self,
     query_prop: &'a QueryProperty,
@@ -27,14 +36,28 @@ pub trait Parser {
   ) -> Result<(), Error>;
 }
 
-type ParsersInner<'a> = FnvHashMap<&'a str, &'a dyn Parser>;
+/// Registry of setting parsers, sharing their ownership.
+pub struct Parsers(Registry<Arc<dyn Parser>>);
 
-pub struct Parsers<'a>(ParsersInner<'a>);
+impl Parsers {
+  /// Creates a registry without any parser.
+  pub fn empty() -> Self { Self(Registry::empty()) }
 
-impl<'a> Parsers<'a> {
-  pub fn empty() -> Self { Self(Default::default()) }
+  /// Creates a registry of the builtin parsers.
+  pub fn builtin() -> std::result::Result<Self, RegistryErr> {
+    let mut parsers = Self::empty();
+    parsers.push(align_style::AlignStyleParser)?;
+    parsers.push(cpl::CplParser)?;
+    parsers.push(directive_prefix::DirectivePrefix)?;
+    parsers.push(ignore_query::IgnoreQuery)?;
+    parsers.push(ignored::Ignored)?;
+    parsers.push(indent_rule::IndentRuleParser)?;
+    parsers.push(indent_style::IndentStyle)?;
+    parsers.push(verbatim::Verbatim)?;
+    Ok(parsers)
+  }
 
-  pub fn parse<'b, 'tree>(
+  pub(crate) fn parse<'b, 'tree>(
     &self,
     query_prop: &'b QueryProperty,
     scope: Scope,
@@ -50,30 +73,27 @@ impl<'a> Parsers<'a> {
     )
   }
 
-  pub fn push(&mut self, parser: &'a impl Parser) -> Option<&'a dyn Parser> {
-    self.0.insert(parser.setting(), parser)
+  /// Registers `parser` under the key of its setting.
+  #[inline]
+  pub fn push(
+    &mut self,
+    parser: impl Parser + 'static,
+  ) -> std::result::Result<(), RegistryErr> {
+    self.push_shared(Arc::new(parser))
   }
-}
-
-impl<'a> Default for Parsers<'a> {
-  fn default() -> Self {
-    let mut parsers = Self::empty();
-
-    macro_rules! insert {
-      ($($parser:path),+ $(,)?) => {{
-        $(assert!(parsers.push(&$parser).is_none());)+
-      }};
-    }
 
-    insert!(
-      cpl::CplParser,
-      ignored::Ignored,
-      indent_rule::IndentRuleParser,
-      indent_style::IndentStyle,
-    );
-
-    parsers
+  /// Registers the shared `parser` under the key of its setting.
+  pub fn push_shared(
+    &mut self,
+    parser: Arc<dyn Parser>,
+  ) -> std::result::Result<(), RegistryErr> {
+    let key = parser.setting().to_string();
+    self.0.insert(&key, parser)
   }
+
+  /// Returns the keys of the settings in alphabetical order.
+  #[inline]
+  pub fn keys(&self) -> impl Iterator<Item = &str> + '_ { self.0.names() }
 }
 
 mod prelude {
@@ -0,0 +1,80 @@
+use crate::settings::parsers::prelude::*;
+
+pub struct Verbatim;
+
+impl Parser for Verbatim {
+  fn setting(&self) -> &'static str { "verbatim" }
+
+  fn parse<'a, 'tree>(
+    &self,
+    query_prop: &'a QueryProperty,
+    scope: Scope,
+    nodes_provider: &Provider<'_, 'tree>,
+    settings: &mut Settings<'a, 'tree>,
+  ) -> Result<(), Error> {
+    let cap_ix = match query_prop.capture_id {
+      None => {
+        let kinds = query_prop
+          .value
+          .as_ref()
+          .ok_or_else(|| Error::value("node kinds", "no value"))?;
+        if let Some(old_kinds) = settings.set_verbatim(kinds, scope) {
+          log::warn!(
+            "\"verbatim\" overwritten {scope}ly; old kinds were \
+             \"{old_kinds}\"",
+          );
+        }
+        log::trace!("{scope}ly set \"verbatim\" to \"{kinds}\"");
+        return Ok(());
+      }
+      Some(cap_ix) => cap_ix,
+    };
+
+    if let Some(value) = query_prop.value.as_ref() {
+      bail!(Error::value("no value", format!("\"{value}\"")));
+    }
+
+    nodes_provider.nodes_for_cap_ix(cap_ix.try_into().unwrap()).for_each(
+      |node| {
+        if settings.node_entry(node).or_default().set_verbatim(true) {
+          log::warn!("\"verbatim\" overwritten for {}", NodeDisplayer(node));
+        }
+        log::trace!("keeping {} verbatim", NodeDisplayer(node));
+      },
+    );
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::query_testing::prelude::*;
+
+  fn cook(query_src: &str) -> std::result::Result<String, String> {
+    cook_with(
+      r#"fn f() { g("a\nb", x); }"#,
+      query_src,
+      |_| {},
+      Options::default(),
+    )
+    .map(|out| out.to_string())
+    .map_err(|err| err.to_string())
+  }
+
+  #[test]
+  fn verbatim() {
+    let spacer = "((function_item) @fn (#spacer! @fn))";
+    assert!(cook(spacer).unwrap_err().contains("(string_literal)"));
+    assert_eq!(
+      cook(&format!(
+        r#"(#set! verbatim "raw_string_literal string_literal") {spacer}"#
+      )),
+      Ok(r#"fn f ( ) { g ( "a\nb" , x ) ; }"#.into()),
+    );
+    assert_eq!(
+      cook(&format!(r#"((string_literal) @s (#set! @s verbatim)) {spacer}"#)),
+      Ok(r#"fn f ( ) { g ( "a\nb" , x ) ; }"#.into()),
+    );
+  }
+}
@@ -0,0 +1,24 @@
+use super::{Syntax, Token};
+
+/// Error of [`verify`].
+///
+/// [`verify`]: crate::verify::verify
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum Error {
+  /// The output has a token beyond the ones of the source.
+  #[error("token {0} appeared in output")]
+  Added(Token),
+
+  /// The output lacks a token of the source.
+  #[error("token {0} vanished from output")]
+  Removed(Token),
+
+  /// The output has a new syntax error.
+  #[error("new {0} in output")]
+  Syntax(Syntax),
+
+  /// The token `old` of the source became `new` in the output.
+  #[allow(missing_docs)]
+  #[error("token {old} became {new}")]
+  Token { old: Token, new: Token },
+}
@@ -0,0 +1,224 @@
+mod err;
+
+pub use err::Error;
+use {
+  crate::node_utils::Walker,
+  error_stack::{bail, Result},
+  ropey::RopeSlice,
+  std::fmt,
+  tree_sitter::{Node, Point, Tree},
+};
+
+/// Location displayed as one-based `row:column`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Location(pub Point);
+
+impl fmt::Display for Location {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}", self.0.row + 1, self.0.column + 1)
+  }
+}
+
+/// Non-whitespace leaf of a tree, or node holding text that none of its
+/// children cover, such as the content of a string literal.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token {
+  /// Kind of the leaf.
+  pub kind: &'static str,
+
+  /// Text of the leaf.
+  pub text: String,
+
+  /// Location of the start of the leaf.
+  pub location: Location,
+}
+
+impl Token {
+  /// Returns whether `self` and `other` only differ by their location.
+  #[inline]
+  fn matches(&self, other: &Self) -> bool {
+    self.kind == other.kind && self.text == other.text
+  }
+}
+
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?} ({}) at {}", self.text, self.kind, self.location)
+  }
+}
+
+/// `ERROR` or `MISSING` node of a tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Syntax {
+  /// Kind of the missing node, if it is not an `ERROR` one.
+  pub missing: Option<&'static str>,
+
+  /// Location of the start of the node.
+  pub location: Location,
+}
+
+impl Syntax {
+  /// Returns whether `self` and `other` only differ by their location.
+  #[inline]
+  fn matches(&self, other: &Self) -> bool { self.missing == other.missing }
+}
+
+impl fmt::Display for Syntax {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.missing {
+      None => write!(f, "ERROR node at {}", self.location),
+      Some(kind) => write!(f, "MISSING {kind:?} node at {}", self.location),
+    }
+  }
+}
+
+/// Returns whether `node`, parsed from `src`, holds non-whitespace text which
+/// none of its children cover, as hidden tokens do.
+fn has_hidden_text(node: &Node<'_>, src: RopeSlice<'_>) -> bool {
+  let mut cursor = node.walk();
+  let mut start = node.start_byte();
+  let mut ends = node
+    .children(&mut cursor)
+    .map(|child| (child.start_byte(), child.end_byte()))
+    .chain([(node.end_byte(), node.end_byte())]);
+  ends.any(|(end, next)| {
+    let gap = start..end.max(start);
+    start = next.max(start);
+    src.byte_slice(gap).chars().any(|c| !c.is_whitespace())
+  })
+}
+
+/// Returns the `ERROR` and `MISSING` nodes, and the tokens of `tree`, parsed
+/// from `src`.
+///
+/// Nodes with hidden text are tokens as a whole, so that whitespace inside
+/// them, such as the one of string literals, is compared as well.
+fn scan(tree: &Tree, src: RopeSlice<'_>) -> (Vec<Syntax>, Vec<Token>) {
+  let mut syntax = Vec::new();
+  let mut tokens = Vec::new();
+  let mut cursor = tree.walk();
+  let mut token_end = 0;
+
+  Walker::from(&mut cursor).map(Node::from).for_each(|node| {
+    let location = Location(node.start_position());
+    if node.is_error() || node.is_missing() {
+      let missing = node.is_missing().then(|| node.kind());
+      syntax.push(Syntax { missing, location });
+    }
+    let is_token = match node.child_count() {
+      0 => !node.is_missing(),
+      _ => has_hidden_text(&node, src),
+    };
+    if is_token && node.start_byte() >= token_end {
+      let text = src.byte_slice(node.byte_range()).to_string();
+      if !text.trim().is_empty() {
+        token_end = node.end_byte();
+        tokens.push(Token { kind: node.kind(), text, location });
+      }
+    }
+  });
+
+  (syntax, tokens)
+}
+
+/// Verifies that `new_tree`, parsed from `new_src`, holds the same tokens as
+/// `old_tree`, parsed from `old_src`, and no new syntax error.
+///
+/// Tokens are compared by kind and text, so that whitespace between them may
+/// freely change.
+pub fn verify(
+  old_tree: &Tree,
+  old_src: RopeSlice<'_>,
+  new_tree: &Tree,
+  new_src: RopeSlice<'_>,
+) -> Result<(), Error> {
+  let (old_syntax, old_tokens) = scan(old_tree, old_src);
+  let (new_syntax, new_tokens) = scan(new_tree, new_src);
+
+  let mut old_syntax = old_syntax.iter();
+  for new in new_syntax {
+    if !old_syntax.next().is_some_and(|old| old.matches(&new)) {
+      bail!(Error::Syntax(new));
+    }
+  }
+
+  let mut old_tokens = old_tokens.into_iter();
+  let mut new_tokens = new_tokens.into_iter();
+  loop {
+    match (old_tokens.next(), new_tokens.next()) {
+      (None, None) => break Ok(()),
+      (Some(old), None) => bail!(Error::Removed(old)),
+      (None, Some(new)) => bail!(Error::Added(new)),
+      (Some(old), Some(new)) if !old.matches(&new) => {
+        bail!(Error::Token { old, new })
+      }
+      _ => {}
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    tree_sitter::Parser,
+    tree_sitter_rust::language as rust_lang,
+  };
+
+  fn verify_str(old: &str, new: &str) -> std::result::Result<(), String> {
+    let mut parser = Parser::new();
+    parser.set_language(rust_lang()).unwrap();
+    let old_tree = parser.parse(old, None).unwrap();
+    let new_tree = parser.parse(new, None).unwrap();
+    verify(&old_tree, old.into(), &new_tree, new.into())
+      .map_err(|err| err.current_context().to_string())
+  }
+
+  #[test]
+  fn verify_() {
+    assert_eq!(verify_str("fn f(){g( a );}", "fn f() {\n  g(a);\n}"), Ok(()));
+    assert_eq!(
+      verify_str("fn f(){}", "fnf(){}"),
+      Err(r#"new ERROR node at 1:1 in output"#.into()),
+    );
+    assert_eq!(
+      verify_str("fn f(){g(a);}", "fn f(){g(a)}"),
+      Err(r#"token ";" (;) at 1:12 became "}" (}) at 1:12"#.into()),
+    );
+    assert_eq!(
+      verify_str("fn f(){}//", "fn f(){}"),
+      Err(r#"token "//" (line_comment) at 1:9 vanished from output"#.into()),
+    );
+    assert_eq!(
+      verify_str("fn f(){g(a);}", "fn f(){g(b);}"),
+      Err(
+        r#"token "a" (identifier) at 1:10 became "b" (identifier) at 1:10"#
+          .into()
+      ),
+    );
+    assert_eq!(
+      verify_str("fn f(){g(a);}", "fn f(){g(a);}//"),
+      Err(r#"token "//" (line_comment) at 1:14 appeared in output"#.into()),
+    );
+    assert_eq!(verify_str("fn f(){", "fn f() {"), Ok(()));
+  }
+
+  #[test]
+  fn hidden_text() {
+    assert_eq!(verify_str(r#"g( "a b" );"#, r#"g("a b");"#), Ok(()));
+    assert_eq!(
+      verify_str(r#"g("ab cd");"#, r#"g(" ab cd ");"#),
+      Err(
+        r#"token "\"ab cd\"" (string_literal) at 1:3 became "\" ab cd \"" (string_literal) at 1:3"#
+          .into()
+      ),
+    );
+    assert_eq!(
+      verify_str(r#"g(b"x y");"#, r#"g(b"x  y");"#),
+      Err(
+        r#"token "b\"x y\"" (string_literal) at 1:3 became "b\"x  y\"" (string_literal) at 1:3"#
+          .into()
+      ),
+    );
+  }
+}
@@ -0,0 +1,65 @@
+use std::{
+  fs,
+  io::Write,
+  path::Path,
+  process::{Command, Output, Stdio},
+};
+
+const QUERY: &str = "((function_item) @fn (#spacer! @fn))";
+
+/// Runs `cook` in `dir` with `args`, piping `stdin` to it.
+fn cook(dir: &Path, args: &[&str], stdin: &str) -> (Option<i32>, String) {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_cook"))
+    .current_dir(dir)
+    .args(["-q", "q.scm"])
+    .args(args)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+  let Output { status, stdout, .. } = child.wait_with_output().unwrap();
+  (status.code(), String::from_utf8(stdout).unwrap())
+}
+
+#[test]
+fn exit_codes() {
+  let dir = tempfile::tempdir().unwrap();
+  let dir = dir.path();
+  fs::write(dir.join("q.scm"), QUERY).unwrap();
+  fs::write(dir.join("cooked.rs"), "fn f ( ) { }").unwrap();
+  fs::write(dir.join("raw.rs"), "fn f() {}").unwrap();
+
+  assert_eq!(cook(dir, &["--check", "cooked.rs"], ""), (Some(0), "".into()));
+  assert_eq!(
+    cook(dir, &["--check", "cooked.rs", "raw.rs"], ""),
+    (Some(1), "raw.rs\n".into()),
+  );
+  assert_eq!(
+    cook(dir, &["--check", "missing.rs", "raw.rs"], ""),
+    (Some(2), "raw.rs\n".into()),
+  );
+  assert_eq!(cook(dir, &["--write", "missing.rs"], "").0, Some(2));
+  assert_eq!(fs::read_to_string(dir.join("raw.rs")).unwrap(), "fn f() {}");
+}
+
+#[test]
+fn exit_codes_stdin() {
+  let dir = tempfile::tempdir().unwrap();
+  let dir = dir.path();
+  fs::write(dir.join("q.scm"), QUERY).unwrap();
+  let args = ["-l", "rust", "--check"];
+
+  assert_eq!(cook(dir, &args, "fn f ( ) { }"), (Some(0), "".into()));
+  assert_eq!(cook(dir, &args, "fn f() {}"), (Some(1), "<stdin>\n".into()));
+  assert_eq!(
+    cook(dir, &["-l", "rust"], "fn f() {}"),
+    (Some(0), "fn f ( ) { }".into()),
+  );
+
+  fs::write(dir.join("q.scm"), "((function_item) @fn (#unknown! @fn))")
+    .unwrap();
+  assert_eq!(cook(dir, &args, "fn f() {}"), (Some(2), "".into()));
+  assert_eq!(cook(dir, &["-l", "rust"], "fn f() {}").0, Some(1));
+}